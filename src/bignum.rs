@@ -0,0 +1,263 @@
+//! Integers and decimals of arbitrary size, available with the `bignum` feature.
+//!
+//! They are written with the forced types `<bigint>` and `<decimal>`, like
+//! `<bigint> 340282366920938463463374607431768211456` or `<decimal> 19.99`. Integers without a
+//! forced type that are too big for `i128` and `u128` are stored as [`BigInt`] as well.
+
+use std::{cmp::Ordering, fmt::{Display, Formatter, Result as FmtResult}, str::FromStr};
+use crate::errors::ParseBigNumError;
+
+/// Every limb holds nine decimal digits.
+const BASE: u32 = 1_000_000_000;
+
+/// An integer of arbitrary size.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    /// Groups of nine digits, starting with the least significant one. Zero has no limbs.
+    limbs: Vec<u32>,
+}
+
+/// A decimal number of arbitrary size and precision, stored as integer mantissa and the number of
+/// digits after the decimal point.
+///
+/// Values are compared by their fields, so `1.50` and `1.5` are not equal. This keeps the
+/// precision they were written with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    mantissa: BigInt,
+    scale: u32,
+}
+
+impl BigInt {
+    /// Returns whether the integer is less than zero.
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Returns the integer as `i128` if it fits.
+    pub fn to_i128(&self) -> Option<i128> {
+        let magnitude = self.magnitude()?;
+        match self.negative {
+            true if magnitude == i128::MIN.unsigned_abs() => Some(i128::MIN),
+            true => i128::try_from(magnitude).ok().map(|n| -n),
+            false => i128::try_from(magnitude).ok()
+        }
+    }
+
+    /// Returns the integer as `u128` if it fits.
+    pub fn to_u128(&self) -> Option<u128> {
+        match self.negative {
+            true => None,
+            false => self.magnitude()
+        }
+    }
+
+    fn magnitude(&self) -> Option<u128> {
+        self.limbs.iter().rev().try_fold(0u128, |acc, limb| acc.checked_mul(u128::from(BASE))?.checked_add(u128::from(*limb)))
+    }
+
+    /// Returns the digits without sign.
+    fn digits(&self) -> String {
+        let Some((last, rest)) = self.limbs.split_last() else {
+            return String::from("0");
+        };
+        let mut res = last.to_string();
+        rest.iter().rev().for_each(|limb| res.push_str(&format!("{limb:09}")));
+        res
+    }
+
+    fn from_magnitude(negative: bool, mut magnitude: u128) -> Self {
+        let mut limbs = Vec::new();
+        while magnitude != 0 {
+            limbs.push(u32::try_from(magnitude % u128::from(BASE)).expect("Limbs should always be less than the base"));
+            magnitude /= u128::from(BASE);
+        }
+        Self{negative: negative && !limbs.is_empty(), limbs}
+    }
+}
+
+impl Decimal {
+    /// Creates a decimal with the value `mantissa / 10^scale`.
+    pub fn new(mantissa: BigInt, scale: u32) -> Self {
+        Self{mantissa, scale}
+    }
+
+    /// Returns the digits of the decimal as integer.
+    pub fn mantissa(&self) -> &BigInt {
+        &self.mantissa
+    }
+
+    /// Returns the number of digits after the decimal point.
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Returns the closest `f64` to the decimal.
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().expect("Decimals should always be valid floats")
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(n: i128) -> Self {
+        Self::from_magnitude(n < 0, n.unsigned_abs())
+    }
+}
+
+impl From<u128> for BigInt {
+    fn from(n: u128) -> Self {
+        Self::from_magnitude(false, n)
+    }
+}
+
+impl From<BigInt> for Decimal {
+    fn from(n: BigInt) -> Self {
+        Self::new(n, 0)
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = ParseBigNumError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = split_sign(s);
+        if digits.is_empty() {
+            return Err(ParseBigNumError::Empty);
+        }
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseBigNumError::InvalidDigit);
+        }
+        let mut limbs: Vec<u32> = digits.as_bytes()
+            .rchunks(9)
+            .map(|chunk| chunk.iter().fold(0, |acc, b| acc * 10 + u32::from(b - b'0')))
+            .collect();
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        Ok(Self{negative: negative && !limbs.is_empty(), limbs})
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = ParseBigNumError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, value) = split_sign(s);
+        let (value, exponent) = match value.split_once(['e', 'E']) {
+            Some((value, exponent)) => (value, exponent.parse::<i16>().map_err(|_| ParseBigNumError::InvalidExponent)?),
+            None => (value, 0)
+        };
+        let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(ParseBigNumError::Empty);
+        }
+        let mut digits = format!("{whole}{fraction}");
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseBigNumError::InvalidDigit);
+        }
+        let mut scale = i64::try_from(fraction.len()).map_err(|_| ParseBigNumError::InvalidDigit)? - i64::from(exponent);
+        if scale < 0 {
+            digits.push_str(&"0".repeat(scale.unsigned_abs().try_into().map_err(|_| ParseBigNumError::InvalidExponent)?));
+            scale = 0;
+        }
+        let mut mantissa = digits.parse::<BigInt>()?;
+        mantissa.negative = negative && !mantissa.limbs.is_empty();
+        Ok(Self{mantissa, scale: u32::try_from(scale).map_err(|_| ParseBigNumError::InvalidExponent)?})
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.digits())
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if self.mantissa.negative {
+            write!(f, "-")?;
+        }
+        let digits = self.mantissa.digits();
+        let scale = usize::try_from(self.scale).expect("Scale should fit into usize");
+        if scale == 0 {
+            return write!(f, "{digits}");
+        }
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{whole}.{fraction}")
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let magnitude = self.limbs.len().cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()));
+        match (self.negative, other.negative) {
+            (false, false) => magnitude,
+            (true, true) => magnitude.reverse(),
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater
+        }
+    }
+}
+
+/// Splits a leading `-` or `+` from the value.
+fn split_sign(value: &str) -> (bool, &str) {
+    match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BigInt, Decimal};
+    use crate::errors::ParseBigNumError;
+
+    #[test]
+    fn big_int_round_trip() {
+        for value in ["0", "7", "-7", "1000000000", "340282366920938463463374607431768211456", "-123456789012345678901234567890"] {
+            assert_eq!(value.parse::<BigInt>().unwrap().to_string(), value);
+        }
+        assert_eq!("+0042".parse::<BigInt>().unwrap().to_string(), "42");
+        assert_eq!("-0".parse::<BigInt>().unwrap().to_string(), "0");
+    }
+
+    #[test]
+    fn big_int_conversions() {
+        assert_eq!(BigInt::from(i128::MIN).to_i128(), Some(i128::MIN));
+        assert_eq!(BigInt::from(u128::MAX).to_u128(), Some(u128::MAX));
+        assert_eq!(BigInt::from(u128::MAX).to_i128(), None);
+        assert_eq!(BigInt::from(-1i128).to_u128(), None);
+        assert!("-2".parse::<BigInt>().unwrap() < "1".parse::<BigInt>().unwrap());
+    }
+
+    #[test]
+    fn decimal_round_trip() {
+        for value in ["0", "19.99", "-0.05", "1.50", "123456789012345678901234567890.123456789"] {
+            assert_eq!(value.parse::<Decimal>().unwrap().to_string(), value);
+        }
+        assert_eq!("1.5e2".parse::<Decimal>().unwrap().to_string(), "150");
+        assert_eq!("15e-3".parse::<Decimal>().unwrap().to_string(), "0.015");
+        assert_eq!(".5".parse::<Decimal>().unwrap().to_string(), "0.5");
+    }
+
+    #[test]
+    fn rejects_invalid_numbers() {
+        assert!(matches!("".parse::<BigInt>(), Err(ParseBigNumError::Empty)));
+        assert!(matches!("-".parse::<BigInt>(), Err(ParseBigNumError::Empty)));
+        assert!(matches!("12a".parse::<BigInt>(), Err(ParseBigNumError::InvalidDigit)));
+        assert!(matches!("1.5".parse::<BigInt>(), Err(ParseBigNumError::InvalidDigit)));
+        assert!(matches!(".".parse::<Decimal>(), Err(ParseBigNumError::Empty)));
+        assert!(matches!("1.2.3".parse::<Decimal>(), Err(ParseBigNumError::InvalidDigit)));
+        assert!(matches!("1e".parse::<Decimal>(), Err(ParseBigNumError::InvalidExponent)));
+    }
+}
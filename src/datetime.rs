@@ -0,0 +1,337 @@
+//! Dates and times as defined by [RFC 3339](https://www.rfc-editor.org/rfc/rfc3339).
+//!
+//! They are written without quotes, like `2024-05-01`, `12:30:00`, `2024-05-01T12:30:00Z` or
+//! `2024-05-01T12:30:00.5+02:00`. Dates and times without an offset are
+//! [`LocalDateTime`]s.
+
+use std::{fmt::{Display, Formatter, Result as FmtResult}, time::{Duration, SystemTime, UNIX_EPOCH}};
+use crate::Element;
+
+/// A calendar date like `2024-05-01`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Date {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+/// A time of day like `12:30:00` or `12:30:00.25`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Time {
+    hour: u8,
+    minute: u8,
+    second: u8,
+    nanosecond: u32,
+}
+
+/// A point in time with an offset from UTC, like `2024-05-01T12:30:00+02:00`.
+///
+/// Values are compared by their fields, so the same point in time with different offsets is not
+/// equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DateTime {
+    date: Date,
+    time: Time,
+    offset_minutes: i16,
+}
+
+/// A date and time without an offset, like `2024-05-01T12:30:00`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LocalDateTime {
+    date: Date,
+    time: Time,
+}
+
+impl Date {
+    /// Creates a date. Returns `None` if the day does not exist.
+    pub fn new(year: u16, month: u8, day: u8) -> Option<Self> {
+        if year > 9999 || !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(Self{year, month, day})
+    }
+
+    /// Returns the year.
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// Returns the month, starting at 1.
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// Returns the day of the month, starting at 1.
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// Returns the number of days since 1970-01-01.
+    fn days_since_epoch(&self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = i64::from(self.month);
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+}
+
+impl Time {
+    /// Creates a time. A second of 60 is allowed for leap seconds. Returns `None` if the time does
+    /// not exist.
+    pub fn new(hour: u8, minute: u8, second: u8, nanosecond: u32) -> Option<Self> {
+        if hour > 23 || minute > 59 || second > 60 || nanosecond >= 1_000_000_000 {
+            return None;
+        }
+        Some(Self{hour, minute, second, nanosecond})
+    }
+
+    /// Returns the hour.
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    /// Returns the minute.
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    /// Returns the second.
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    /// Returns the fraction of the second in nanoseconds.
+    pub fn nanosecond(&self) -> u32 {
+        self.nanosecond
+    }
+}
+
+impl DateTime {
+    /// Creates a date and time with an offset from UTC in minutes. Returns `None` if the offset
+    /// is not less than a day.
+    pub fn new(date: Date, time: Time, offset_minutes: i16) -> Option<Self> {
+        if offset_minutes.abs() >= 24 * 60 {
+            return None;
+        }
+        Some(Self{date, time, offset_minutes})
+    }
+
+    /// Returns the date.
+    pub fn date(&self) -> Date {
+        self.date
+    }
+
+    /// Returns the time of day.
+    pub fn time(&self) -> Time {
+        self.time
+    }
+
+    /// Returns the offset from UTC in minutes.
+    pub fn offset_minutes(&self) -> i16 {
+        self.offset_minutes
+    }
+
+    /// Returns the point in time as [`SystemTime`], or `None` if the platform can not represent
+    /// it. Leap seconds are treated like the second before them.
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        let seconds = self.date.days_since_epoch() * 86_400
+            + i64::from(self.time.hour) * 3600
+            + i64::from(self.time.minute) * 60
+            + i64::from(self.time.second.min(59))
+            - i64::from(self.offset_minutes) * 60;
+        let nanos = Duration::from_nanos(u64::from(self.time.nanosecond));
+        let time = match u64::try_from(seconds) {
+            Ok(seconds) => UNIX_EPOCH.checked_add(Duration::from_secs(seconds)),
+            Err(_) => UNIX_EPOCH.checked_sub(Duration::from_secs(seconds.unsigned_abs()))
+        };
+        time?.checked_add(nanos)
+    }
+}
+
+impl LocalDateTime {
+    /// Creates a date and time without an offset.
+    pub fn new(date: Date, time: Time) -> Self {
+        Self{date, time}
+    }
+
+    /// Returns the date.
+    pub fn date(&self) -> Date {
+        self.date
+    }
+
+    /// Returns the time of day.
+    pub fn time(&self) -> Time {
+        self.time
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl Display for Time {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)?;
+        if self.nanosecond != 0 {
+            write!(f, ".{}", format!("{:09}", self.nanosecond).trim_end_matches('0'))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for DateTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}T{}", self.date, self.time)?;
+        match self.offset_minutes {
+            0 => write!(f, "Z"),
+            offset => write!(f, "{}{:02}:{:02}", if offset < 0 { '-' } else { '+' }, offset.abs() / 60, offset.abs() % 60)
+        }
+    }
+}
+
+impl Display for LocalDateTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}T{}", self.date, self.time)
+    }
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    }
+}
+
+/// Checks if a value without quotes is meant to be a date or time, like `2024-…` or `12:…`.
+pub(crate) fn is_literal(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    let digits = |n: usize| bytes.len() > n && bytes[..n].iter().all(u8::is_ascii_digit);
+    (digits(4) && bytes[4] == b'-') || (digits(2) && bytes[2] == b':')
+}
+
+/// Parses a date, time, date and time with offset, or local date and time.
+pub(crate) fn parse_literal(value: &str) -> Option<Element> {
+    if !value.is_ascii() {
+        return None;
+    }
+    if value.as_bytes().get(2) == Some(&b':') {
+        return parse_time(value).map(Element::PmlTime);
+    }
+    if value.len() <= 10 {
+        return parse_date(value).map(Element::PmlDate);
+    }
+    let (date, rest) = value.split_at(10);
+    let rest = rest.strip_prefix(['T', 't'])?;
+    let date = parse_date(date)?;
+    let Some(offset_start) = rest.find(['Z', 'z', '+', '-']) else {
+        return Some(Element::PmlLocalDateTime(LocalDateTime::new(date, parse_time(rest)?)));
+    };
+    let (time, offset) = rest.split_at(offset_start);
+    Some(Element::PmlDateTime(DateTime::new(date, parse_time(time)?, parse_offset(offset)?)?))
+}
+
+fn parse_date(value: &str) -> Option<Date> {
+    let [year, month, day] = split_numbers(value, '-')?;
+    if year.len() != 4 {
+        return None;
+    }
+    Date::new(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?)
+}
+
+fn parse_time(value: &str) -> Option<Time> {
+    let (value, fraction) = match value.split_once('.') {
+        Some((value, fraction)) => (value, Some(fraction)),
+        None => (value, None)
+    };
+    let [hour, minute, second] = split_numbers(value, ':')?;
+    if hour.len() != 2 {
+        return None;
+    }
+    let nanosecond = match fraction {
+        Some(f) if f.is_empty() || f.len() > 9 || !f.bytes().all(|b| b.is_ascii_digit()) => return None,
+        Some(f) => f.parse::<u32>().ok()? * 10u32.pow(9 - u32::try_from(f.len()).ok()?),
+        None => 0
+    };
+    Time::new(hour.parse().ok()?, minute.parse().ok()?, second.parse().ok()?, nanosecond)
+}
+
+fn parse_offset(value: &str) -> Option<i16> {
+    if value == "Z" || value == "z" {
+        return Some(0);
+    }
+    let (sign, value) = match value.split_at(1) {
+        ("+", value) => (1, value),
+        ("-", value) => (-1, value),
+        _ => return None
+    };
+    let (hours, minutes) = value.split_once(':')?;
+    if hours.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    let (hours, minutes) = (hours.parse::<i16>().ok()?, minutes.parse::<i16>().ok()?);
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 60 + minutes))
+}
+
+/// Splits the value into three numbers with two digits, except for the first one, which may be
+/// longer.
+fn split_numbers(value: &str, separator: char) -> Option<[&str; 3]> {
+    let mut parts = value.split(separator);
+    let res = [parts.next()?, parts.next()?, parts.next()?];
+    let valid = parts.next().is_none()
+        && res.iter().all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()))
+        && res[1].len() == 2 && res[2].len() == 2 && res[0].len() >= 2;
+    valid.then_some(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Date, DateTime, Time};
+    use crate::{parse, ParseError};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn parses_dates_and_times() {
+        let config = parse::string("d = 2024-02-29; t = 12:30:00.250; dt = 2024-05-01T12:30:00+02:00;").unwrap();
+        assert_eq!(config.get::<Date>("d").unwrap(), Date::new(2024, 2, 29).unwrap());
+        assert_eq!(config.get::<Time>("t").unwrap(), Time::new(12, 30, 0, 250_000_000).unwrap());
+        let dt = config.get::<DateTime>("dt").unwrap();
+        assert_eq!(dt.offset_minutes(), 120);
+        assert_eq!(dt.time().hour(), 12);
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        assert!(matches!(parse::string("d = 2023-02-29;"), Err(ParseError::InvalidDateTime{..})));
+        assert!(matches!(parse::string("t = 24:00:00;"), Err(ParseError::InvalidDateTime{..})));
+        assert!(Date::new(2024, 13, 1).is_none());
+    }
+
+    #[test]
+    fn converts_to_system_time() {
+        let config = parse::string("a = 1970-01-01T00:00:01.5Z; b = 1970-01-01T01:00:00+01:00; c = 1969-12-31T23:59:59Z;").unwrap();
+        assert_eq!(config.get::<SystemTime>("a").unwrap(), UNIX_EPOCH + Duration::from_millis(1500));
+        assert_eq!(config.get::<SystemTime>("b").unwrap(), UNIX_EPOCH);
+        assert_eq!(config.get::<SystemTime>("c").unwrap(), UNIX_EPOCH - Duration::from_secs(1));
+    }
+
+    #[test]
+    fn converts_extreme_dates_without_panicking() {
+        let earliest = DateTime::new(Date::new(0, 1, 1).unwrap(), Time::new(0, 0, 0, 0).unwrap(), 0).unwrap();
+        let latest = DateTime::new(Date::new(9999, 12, 31).unwrap(), Time::new(23, 59, 59, 999_999_999).unwrap(), -1439).unwrap();
+        for dt in [earliest, latest] {
+            if let Some(time) = dt.to_system_time() {
+                assert_eq!(time > UNIX_EPOCH, dt.date().year() > 1970);
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Types for comparing two [`PmlStructs`](crate::PmlStruct) structurally.
+//!
+//! See [`diff`] for more information.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use crate::{Element, ArrayElement, PmlStruct, path, impls::serialize::literal};
+
+/// The differences between two structs, ordered by path.
+#[derive(Debug, Clone, Default)]
+pub struct Diff {
+    /// All values that differ.
+    pub changes: Vec<Change>,
+}
+
+/// A single value that differs between two structs.
+#[derive(Debug, Clone)]
+pub struct Change {
+    /// The full path of the value. Elements of struct arrays are denoted by their index, e.g.
+    /// `servers[0].port`, and values of maps by their key in quotes, e.g. `hosts["example.com"]`.
+    pub path: String,
+    /// How the value differs.
+    pub kind: ChangeKind,
+}
+
+/// The ways a value can differ between two structs.
+#[derive(Debug, Clone)]
+pub enum ChangeKind {
+    /// The value only exists in the new struct.
+    Added(Element),
+    /// The value only exists in the old struct.
+    Removed(Element),
+    /// The value exists in both structs with the same type, but is different.
+    Changed {
+        /// The value in the old struct.
+        old: Element,
+        /// The value in the new struct.
+        new: Element,
+    },
+    /// The value exists in both structs, but has a different type, e.g. `u8` and `u16`.
+    TypeChanged {
+        /// The value in the old struct.
+        old: Element,
+        /// The value in the new struct.
+        new: Element,
+    },
+}
+
+/// Compares two structs and returns all values that differ.
+///
+/// Nested structs, maps and structs in struct arrays are compared value by value, all other arrays
+/// are compared as a whole.
+pub fn diff(old: &PmlStruct, new: &PmlStruct) -> Diff {
+    let mut res = Diff::default();
+    diff_structs(old, new, "", &mut res.changes);
+    res
+}
+
+impl Diff {
+    /// Returns `true` if there are no differences.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns the differences in a machine-readable form, as a PML struct array called `changes`.
+    ///
+    /// Every change is a struct with an `op` that is one of `added`, `removed`, `changed` or
+    /// `type_changed`, the `path` of the value, and the values `old` and/or `new`.
+    pub fn to_pml_string(&self) -> String {
+        let changes = self.changes.iter().map(|change| {
+            let mut s = PmlStruct::new();
+            let (op, old, new) = match &change.kind {
+                ChangeKind::Added(new) => ("added", None, Some(new)),
+                ChangeKind::Removed(old) => ("removed", Some(old), None),
+                ChangeKind::Changed{old, new} => ("changed", Some(old), Some(new)),
+                ChangeKind::TypeChanged{old, new} => ("type_changed", Some(old), Some(new))
+            };
+            s.elements.insert(String::from("op"), String::from(op).into());
+            s.elements.insert(String::from("path"), change.path.clone().into());
+            if let Some(old) = old {
+                s.elements.insert(String::from("old"), old.clone());
+            }
+            if let Some(new) = new {
+                s.elements.insert(String::from("new"), new.clone());
+            }
+            s
+        }).collect();
+        let mut res = PmlStruct::new();
+        res.elements.insert(String::from("changes"), Element::PmlArray(ArrayElement::PmlStruct(changes)));
+        res.to_pml_string()
+    }
+}
+
+impl Display for Diff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match &self.kind {
+            ChangeKind::Added(new) => write!(f, "+ {} = {}", self.path, literal(new, None)),
+            ChangeKind::Removed(old) => write!(f, "- {} = {}", self.path, literal(old, None)),
+            ChangeKind::Changed{old, new} => write!(f, "~ {}: {} -> {}", self.path, literal(old, None), literal(new, None)),
+            ChangeKind::TypeChanged{old, new} => write!(f, "! {}: {} -> {} (type changed from {} to {})", self.path, literal(old, None), literal(new, None), old.type_name(), new.type_name())
+        }
+    }
+}
+
+fn diff_structs(old: &PmlStruct, new: &PmlStruct, path: &str, changes: &mut Vec<Change>) {
+    let mut keys: Vec<&String> = old.elements.keys().chain(new.elements.keys().filter(|k| !old.elements.contains_key(*k))).collect();
+    keys.sort();
+    for key in keys {
+        let path = path::join(path, key);
+        match (old.elements.get(key), new.elements.get(key)) {
+            (Some(old), Some(new)) => diff_elements(old, new, path, changes),
+            (Some(old), None) => changes.push(Change{path, kind: ChangeKind::Removed(old.clone())}),
+            (None, Some(new)) => changes.push(Change{path, kind: ChangeKind::Added(new.clone())}),
+            (None, None) => unreachable!("The key was taken from one of the structs")
+        }
+    }
+}
+
+fn diff_elements(old: &Element, new: &Element, path: String, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Element::PmlStruct(o), Element::PmlStruct(n)) => diff_structs(o, n, &path, changes),
+        (Element::PmlMap(o), Element::PmlMap(n)) => {
+            let mut keys: Vec<&String> = o.keys().chain(n.keys().filter(|k| !o.contains_key(*k))).collect();
+            keys.sort();
+            for key in keys {
+                let path = path::join_map_key(&path, key);
+                match (o.get(key), n.get(key)) {
+                    (Some(o), Some(n)) => diff_elements(o, n, path, changes),
+                    (Some(o), None) => changes.push(Change{path, kind: ChangeKind::Removed(o.clone())}),
+                    (None, Some(n)) => changes.push(Change{path, kind: ChangeKind::Added(n.clone())}),
+                    (None, None) => unreachable!("The key was taken from one of the maps")
+                }
+            }
+        }
+        (Element::PmlArray(ArrayElement::PmlStruct(o)), Element::PmlArray(ArrayElement::PmlStruct(n))) => {
+            for i in 0..o.len().max(n.len()) {
+                let path = format!("{path}[{i}]");
+                match (o.get(i), n.get(i)) {
+                    (Some(o), Some(n)) => diff_structs(o, n, &path, changes),
+                    (Some(o), None) => changes.push(Change{path, kind: ChangeKind::Removed(o.clone().into())}),
+                    (None, Some(n)) => changes.push(Change{path, kind: ChangeKind::Added(n.clone().into())}),
+                    (None, None) => unreachable!("The index is smaller than one of the lengths")
+                }
+            }
+        }
+        (o, n) if o.type_name() != n.type_name() => changes.push(Change{path, kind: ChangeKind::TypeChanged{old: o.clone(), new: n.clone()}}),
+        (o, n) if o != n => changes.push(Change{path, kind: ChangeKind::Changed{old: o.clone(), new: n.clone()}}),
+        _ => ()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, PmlStruct};
+    use super::{diff, ChangeKind};
+
+    fn pml(input: &str) -> PmlStruct {
+        parse::string(input).expect("The input should be valid")
+    }
+
+    #[test]
+    fn reports_changes_by_path() {
+        let old = pml("name = \"a\"; port = <u16> 80; db = { host = \"x\"; }; servers = [{id = <u8> 1;}];");
+        let new = pml("name = \"b\"; port = <u32> 80; db = { host = \"x\"; user = \"u\"; }; servers = [{id = <u8> 1;}, {id = <u8> 2;}];");
+        let res = diff(&old, &new);
+        let paths: Vec<&str> = res.changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, ["db.user", "name", "port", "servers[1]"]);
+        assert!(matches!(res.changes[0].kind, ChangeKind::Added(_)));
+        assert!(matches!(res.changes[1].kind, ChangeKind::Changed{..}));
+        assert!(matches!(res.changes[2].kind, ChangeKind::TypeChanged{..}));
+        assert!(matches!(res.changes[3].kind, ChangeKind::Added(_)));
+        assert_eq!(res.to_string().lines().nth(1), Some("~ name: \"a\" -> \"b\""));
+    }
+
+    #[test]
+    fn compares_map_entries() {
+        let old = pml("ports = <map<u16>> { \"a.com\" = 80; \"b.com\" = 81; };");
+        let new = pml("ports = <map<u16>> { \"a.com\" = 8080; \"c.com\" = 82; };");
+        let res = diff(&old, &new);
+        let paths: Vec<&str> = res.changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, ["ports[\"a.com\"]", "ports[\"b.com\"]", "ports[\"c.com\"]"]);
+        assert!(matches!(res.changes[1].kind, ChangeKind::Removed(_)));
+    }
+
+    #[test]
+    fn equal_structs_have_no_changes() {
+        assert!(diff(&pml("a = [1, \"x\"]; m = <map<str>> {};"), &pml("a = [1, \"x\"]; m = <map<str>> {};")).is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Decodes hex digits like `deadBEEF`. Every byte needs two digits.
+pub(crate) fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if value.len() % 2 != 0 || !value.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..value.len()).step_by(2).map(|i| u8::from_str_radix(&value[i..i + 2], 16).ok()).collect()
+}
+
+/// Decodes base64 with the standard alphabet. The padding at the end is optional.
+pub(crate) fn decode_base64(value: &str) -> Option<Vec<u8>> {
+    let value = value.trim_end_matches('=');
+    if value.len() % 4 == 1 {
+        return None;
+    }
+    let mut res = Vec::with_capacity(value.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in value.bytes() {
+        let sextet = BASE64_ALPHABET.iter().position(|a| *a == c)?;
+        buffer = buffer << 6 | u32::try_from(sextet).ok()?;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            res.push(u8::try_from(buffer >> bits & 0xFF).ok()?);
+        }
+    }
+    if buffer & ((1 << bits) - 1) != 0 {
+        return None;
+    }
+    Some(res)
+}
+
+/// Encodes the bytes as base64 with the standard alphabet and padding.
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+    let mut res = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let buffer = chunk.iter().enumerate().fold(0u32, |acc, (i, b)| acc | u32::from(*b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                res.push(char::from(BASE64_ALPHABET[(buffer >> (18 - 6 * i) & 0x3F) as usize]));
+            }
+            else {
+                res.push('=');
+            }
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_base64, decode_hex, encode_base64};
+    use crate::{parse, ParseError};
+
+    #[test]
+    fn decodes_hex_and_base64() {
+        assert_eq!(decode_hex("DEADbeef"), Some(vec![0xDE, 0xAD, 0xBE, 0xEF]));
+        assert_eq!(decode_hex("ABC"), None);
+        assert_eq!(decode_base64("3q2+7w=="), Some(vec![0xDE, 0xAD, 0xBE, 0xEF]));
+        assert_eq!(decode_base64("3q2+7w"), Some(vec![0xDE, 0xAD, 0xBE, 0xEF]));
+        assert_eq!(decode_base64("3q*+"), None);
+        assert_eq!(encode_base64(&[0, 1, 2, 3]), "AAECAw==");
+    }
+
+    #[test]
+    fn parses_chars_and_bytes() {
+        let config = parse::string("a = <char> \"ß\"; b = <char> \"\\n\"; c = <bytes> hex\"00ff\"; d = <bytes> base64\"AP8=\";").unwrap();
+        assert_eq!(config.get::<char>("a").unwrap(), 'ß');
+        assert_eq!(config.get::<char>("b").unwrap(), '\n');
+        assert_eq!(config.get::<Vec<u8>>("c").unwrap(), vec![0, 255]);
+        assert_eq!(config.get::<Vec<u8>>("d").unwrap(), vec![0, 255]);
+        assert!(matches!(parse::string("a = <bytes> hex\"0\";"), Err(ParseError::InvalidBytes{..})));
+        assert!(parse::string("a = <char> \"ab\";").is_err());
+    }
+
+    #[test]
+    fn serializes_chars_and_bytes() {
+        let config = parse::string("a = <char> \"\\\"\"; b = <bytes> hex\"00ff\";").unwrap();
+        assert_eq!(parse::string(&config.to_pml_string()).unwrap(), config);
+    }
+}
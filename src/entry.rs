@@ -0,0 +1,171 @@
+//! Types for manipulating single elements of a [`PmlStruct`] in place.
+//!
+//! See [`PmlStruct::entry`] for more information.
+
+use std::collections::hash_map;
+use crate::{Element, PmlStruct};
+
+/// A view into a single element of a [`PmlStruct`], which may either exist or not.
+///
+/// This is constructed by [`PmlStruct::entry`].
+#[derive(Debug)]
+pub enum Entry<'a> {
+    /// The element already exists.
+    Occupied(OccupiedEntry<'a>),
+    /// The element does not exist yet.
+    Vacant(VacantEntry<'a>),
+}
+
+/// A view into an element that already exists.
+#[derive(Debug)]
+pub struct OccupiedEntry<'a> {
+    pub(crate) entry: hash_map::OccupiedEntry<'a, String, Element>,
+}
+
+/// A view into an element that does not exist yet.
+///
+/// Any structs on the way to the element that do not exist will be created when a value is
+/// inserted.
+#[derive(Debug)]
+pub struct VacantEntry<'a> {
+    pub(crate) parent: &'a mut PmlStruct,
+    pub(crate) key: String,
+}
+
+impl<'a> Entry<'a> {
+    /// Ensures a value is in the entry by inserting the default if empty, and returns a mutable
+    /// reference to the value in the entry.
+    pub fn or_insert<T>(self, default: T) -> &'a mut Element
+        where
+        T: Into<Element>
+        {
+            match self {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(default)
+            }
+        }
+
+    /// Ensures a value is in the entry by inserting the result of the default function if empty,
+    /// and returns a mutable reference to the value in the entry.
+    pub fn or_insert_with<T, F>(self, default: F) -> &'a mut Element
+        where
+        T: Into<Element>,
+        F: FnOnce() -> T
+        {
+            match self {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(default())
+            }
+        }
+
+    /// Provides in-place mutable access to an occupied entry before any potential inserts.
+    #[must_use]
+    pub fn and_modify<F>(self, f: F) -> Self
+        where
+        F: FnOnce(&mut Element)
+        {
+            match self {
+                Entry::Occupied(mut entry) => {
+                    f(entry.get_mut());
+                    Entry::Occupied(entry)
+                }
+                Entry::Vacant(entry) => Entry::Vacant(entry)
+            }
+        }
+}
+
+impl<'a> OccupiedEntry<'a> {
+    /// Returns the last segment of the key of the entry.
+    pub fn key(&self) -> &str {
+        self.entry.key()
+    }
+
+    /// Returns a reference to the value in the entry.
+    pub fn get(&self) -> &Element {
+        self.entry.get()
+    }
+
+    /// Returns a mutable reference to the value in the entry.
+    pub fn get_mut(&mut self) -> &mut Element {
+        self.entry.get_mut()
+    }
+
+    /// Converts the entry into a mutable reference to its value.
+    pub fn into_mut(self) -> &'a mut Element {
+        self.entry.into_mut()
+    }
+
+    /// Replaces the value in the entry and returns the old value.
+    pub fn insert<T>(&mut self, value: T) -> Element
+        where
+        T: Into<Element>
+        {
+            self.entry.insert(value.into())
+        }
+
+    /// Removes the element from its struct and returns its value.
+    pub fn remove(self) -> Element {
+        self.entry.remove()
+    }
+}
+
+impl<'a> VacantEntry<'a> {
+    /// Returns the remaining key that will be used when inserting a value. This is relative to the
+    /// deepest struct on the way to the element that already exists.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Inserts the value into the entry, creating any missing structs on the way, and returns a
+    /// mutable reference to it.
+    pub fn insert<T>(self, value: T) -> &'a mut Element
+        where
+        T: Into<Element>
+        {
+            let VacantEntry{parent, key} = self;
+            match key.split_once('.') {
+                None => parent.elements.entry(key).or_insert(value.into()),
+                Some((first, rest)) => match parent.elements.entry(String::from(first)).or_insert_with(|| PmlStruct::new().into()) {
+                    Element::PmlStruct(s) => VacantEntry{parent: s, key: String::from(rest)}.insert(value),
+                    _ => unreachable!("Only vacant keys should be used")
+                }
+            }
+        }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, Element, ParseError};
+    use super::Entry;
+
+    #[test]
+    fn inserts_into_missing_structs() {
+        let mut config = parse::string("server = { port = <u16> 80; };").unwrap();
+        config.entry("server.tls.enabled").unwrap().or_insert(true);
+        config.entry("server.port").unwrap().or_insert(443u16);
+        assert!(config.get::<bool>("server.tls.enabled").unwrap());
+        assert_eq!(config.get::<u16>("server.port").unwrap(), 80);
+    }
+
+    #[test]
+    fn modifies_and_removes_existing_values() {
+        let mut config = parse::string("retries = <u8> 3;").unwrap();
+        config.entry("retries").unwrap()
+            .and_modify(|e| if let Element::PmlU8(n) = e { *n += 1; })
+            .or_insert(0u8);
+        assert_eq!(config.get::<u8>("retries").unwrap(), 4);
+        let Entry::Occupied(entry) = config.entry("retries").unwrap() else {
+            panic!("The entry should exist");
+        };
+        assert_eq!(entry.key(), "retries");
+        assert!(matches!(entry.remove(), Element::PmlU8(4)));
+        assert!(matches!(config.entry("retries"), Ok(Entry::Vacant(_))));
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        let mut config = parse::string("port = <u16> 80;").unwrap();
+        assert!(matches!(config.entry("port.number"), Err(ParseError::NotAStruct(key)) if key == "port"));
+        assert!(matches!(config.entry("a..b"), Err(ParseError::InvalidKey)));
+    }
+}
@@ -0,0 +1,100 @@
+//! Enumerations declared in PML files.
+//!
+//! Enums are declared at the top of a file with `#enum LogLevel: debug, info, warn, error` and
+//! used as forced type, like `level = <LogLevel> warn;`. Values that are not variants of the enum
+//! are rejected while parsing. Enum names have to start with an uppercase letter, so they can not
+//! clash with built-in types.
+//!
+//! Values are stored as [`EnumValue`]. To convert them into Rust enums with
+//! [`get`](crate::PmlStruct::get), implement the conversion with [`pml_enum`](crate::pml_enum).
+
+/// A variant of an enum declared in a PML file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EnumValue {
+    name: String,
+    variant: String,
+}
+
+impl EnumValue {
+    /// Creates a value of the enum with the provided name.
+    pub fn new(name: &str, variant: &str) -> Self {
+        Self{name: name.to_string(), variant: variant.to_string()}
+    }
+
+    /// Returns the name of the enum.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the name of the variant.
+    pub fn variant(&self) -> &str {
+        &self.variant
+    }
+}
+
+/// Implements the conversion from [`Element`](crate::Element) into a Rust enum and
+/// [`FromElement`](crate::FromElement), so it can be returned by [`get`](crate::PmlStruct::get).
+///
+/// Takes the enum and its variants with the names they have in PML, like
+/// `pml_enum!(LogLevel { Debug => "debug", Warn => "warn" });`. Enum values and strings with the
+/// name of a variant are converted, regardless of the name of the enum in PML.
+#[macro_export]
+macro_rules! pml_enum {
+    ($type:ident { $($variant:ident => $name:literal),* $(,)? }) => {
+        impl TryFrom<&$crate::Element> for $type {
+            type Error = $crate::GetError;
+            fn try_from(elem: &$crate::Element) -> Result<Self, $crate::GetError> {
+                let variant = match elem {
+                    $crate::Element::PmlEnum(e) => e.variant(),
+                    $crate::Element::PmlString(s) => s.as_str(),
+                    _ => return Err($crate::GetError::InvalidType)
+                };
+                match variant {
+                    $($name => Ok($type::$variant),)*
+                    _ => Err($crate::GetError::InvalidType)
+                }
+            }
+        }
+
+        impl $crate::FromElement<'_> for $type {
+            fn from_element(elem: &$crate::Element) -> Result<Self, $crate::GetError> {
+                Self::try_from(elem)
+            }
+
+            fn from_copy(elem: $crate::Element) -> Result<Self, $crate::GetError> {
+                Self::try_from(&elem)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EnumValue;
+    use crate::{parse, ParseError};
+
+    #[derive(Debug, PartialEq)]
+    enum LogLevel {
+        Debug,
+        Warn,
+    }
+
+    crate::pml_enum!(LogLevel { Debug => "debug", Warn => "warn" });
+
+    #[test]
+    fn parses_declared_enums() {
+        let config = parse::string("#enum LogLevel: debug, warn\nlevel = <LogLevel> warn;").unwrap();
+        assert_eq!(config.get::<EnumValue>("level").unwrap(), EnumValue::new("LogLevel", "warn"));
+        assert!(matches!(parse::string("#enum LogLevel: debug, warn\nlevel = <LogLevel> info;"), Err(ParseError::UnknownVariant{..})));
+        assert!(matches!(parse::string("level = <LogLevel> warn;"), Err(ParseError::UnknownForcedType{..})));
+    }
+
+    #[test]
+    fn converts_to_generated_enums() {
+        let config = parse::string("#enum LogLevel: debug, warn\nlevel = <LogLevel> warn; name = \"debug\"; other = 1;").unwrap();
+        assert_eq!(config.get::<LogLevel>("level").unwrap(), LogLevel::Warn);
+        assert_eq!(config.get::<LogLevel>("name").unwrap(), LogLevel::Debug);
+        assert_eq!(config.get::<Option<LogLevel>>("missing").unwrap(), None);
+        assert!(config.get::<LogLevel>("other").is_err());
+    }
+}
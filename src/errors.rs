@@ -1,6 +1,8 @@
-//! Module containing any errors that may be returned when parsing, inserting or getting data.
+//! Module containing any errors that may be returned when parsing, loading, inserting, getting,
+//! merging or patching data.
 
 use std::{io::Error as IoError, num::{ParseFloatError, ParseIntError}};
+use crate::{Element, loader::Layer, patch::Operation};
 
 /// Errors that may occur when [parsing](crate::parse) a PML file or [adding](crate::PmlStruct::add) data to a [`PmlStruct`](crate::PmlStruct).
 #[derive(Debug)]
@@ -10,13 +12,99 @@ pub enum ParseError {
         /// The key that was already used.
         key: String,
     },
+    /// The result of an integer expression does not fit into the type of its operands.
+    ArithmeticOverflow {
+        /// The key of the expression.
+        key: String,
+    },
+    /// A struct extends a value that is not a struct.
+    BaseNotAStruct {
+        /// The key of the extending struct.
+        key: String,
+        /// The name of the value it extends.
+        base: String,
+    },
+    /// Structs extend each other in a cycle, like `a = |b| + { ... }; b = |a| + { ... };`.
+    CyclicExtension {
+        /// The keys of the structs in the cycle, each extending the next one and the last one
+        /// extending the first.
+        keys: Vec<String>,
+    },
+    /// An integer expression divides by zero or takes the remainder of a division by zero.
+    DivisionByZero {
+        /// The key of the expression.
+        key: String,
+    },
     /// The file with the provided path could not be opened.
     FileAccess(
         /// The error thrown by [fs](std::fs).
         IoError
     ),
+    /// A function that is called in an expression returned an error.
+    FunctionFailed {
+        /// The key of the expression.
+        key: String,
+        /// The name of the function.
+        function: String,
+        /// The error returned by the function.
+        message: String,
+    },
+    /// A byte string is not valid hex or base64, or uses another encoding.
+    InvalidBytes{
+        /// The line in which the value appears.
+        line: u32,
+        /// The literal value that was provided.
+        value: String
+    },
+    /// A value looks like a date or time, but is not valid.
+    InvalidDateTime{
+        /// The line in which the value appears.
+        line: u32,
+        /// The literal value that was provided.
+        value: String
+    },
+    /// An enum declaration is not valid, or an enum with the same name was already declared.
+    InvalidEnum{
+        /// The line in which the declaration appears.
+        line: u32,
+        /// The declaration that was provided.
+        value: String
+    },
+    /// An escape sequence in a string is not known, or does not describe a valid char.
+    InvalidEscape{
+        /// The line where the backslash of the escape sequence appears.
+        line: u32,
+        /// The column where the backslash of the escape sequence appears.
+        col: u32,
+        /// The escape sequence that was provided.
+        value: String
+    },
+    /// The format spec of a variable in a string does not fit the type of the variable.
+    InvalidFormatSpec{
+        /// The line in which the spec appears.
+        line: u32,
+        /// The spec that was provided.
+        spec: String,
+        /// The type of the variable.
+        value_type: String
+    },
     /// The key is not valid.
     InvalidKey,
+    /// An operator or function of an expression can not be applied to the types of its operands.
+    InvalidOperands {
+        /// The key of the expression.
+        key: String,
+        /// The operator or function together with the types of its operands, like `u8 * str` or
+        /// `upper(u8)`.
+        operation: String,
+    },
+    /// A value has a unit, but either the unit does not exist or the amount is not valid.
+    InvalidUnit{
+        /// The line in which the value appears.
+        line: u32,
+        /// The literal value that was provided.
+        value: String
+    },
     /// The provided PML version is not valid.
     InvalidVersion,
     /// The character is not allowed at that position.
@@ -34,6 +122,14 @@ pub enum ParseError {
     /// The element could not be inserted because the key implies a struct where a non-struct
     /// element was found.
     NotAStruct(String),
+    /// An array has a nullable forced type, like `<u8?> [1, null]`. The values of an array with a
+    /// forced type can not be null.
+    NullableArray {
+        /// The key of the array.
+        key: String,
+        /// The forced type of the array, without the `?`.
+        type_name: String,
+    },
     /// An error occured while parsing a number.
     ParseNumberError{
         /// The line in which the number appears.
@@ -43,8 +139,26 @@ pub enum ParseError {
         /// The error that occured while parsing the number.
         error: ParseNumberError
     },
+    /// A value of an array with a forced type that is resolved later, like a value of `..|hosts|`
+    /// in `<str> [..|hosts|]` or `|port|` in `<u16> [|port|]`, does not have that type. Numbers
+    /// that fit into the type are converted, like in `<u16> [..|ports|]` with `ports = <u8> [80];`.
+    SpreadTypeMismatch {
+        /// The key of the array with the forced type.
+        key: String,
+        /// The forced type of the array, or the type of its arrays if it is nested, like `[u8]`.
+        expected: String,
+        /// The type of the value.
+        found: String,
+    },
     /// The end of the file was reached unexpectedly.
     UnexpectedEOF,
+    /// A struct extends a struct that does not exist.
+    UnknownBase {
+        /// The key of the extending struct.
+        key: String,
+        /// The name of the struct it extends.
+        base: String,
+    },
     /// The provided forced type does not exist.
     UnknownForcedType{
         /// The key of the element that was supposed to be forced.
@@ -52,6 +166,29 @@ pub enum ParseError {
         /// The typename that was provided.
         type_name: String
     },
+    /// A function that is called in an expression does not exist.
+    UnknownFunction{
+        /// The line in which the call appears.
+        line: u32,
+        /// The name of the function.
+        name: String
+    },
+    /// The value is not a variant of the enum used as forced type.
+    UnknownVariant{
+        /// The line in which the value appears.
+        line: u32,
+        /// The name of the enum.
+        name: String,
+        /// The variant that was provided.
+        variant: String
+    },
+    /// A value without quotes is not a known word like `true`, `null` or `inf`.
+    UnknownValue{
+        /// The line in which the value appears.
+        line: u32,
+        /// The literal value that was provided.
+        value: String
+    },
 }
 
 /// Errors that may occur when parsing a number. This can occur because the provided number could
@@ -62,7 +199,24 @@ pub enum ParseNumberError {
     /// The number could not be parsed as a signed or unsigned integer.
     Int(ParseIntError),
     /// The number could not be parsed as a floating point number.
-    Float(ParseFloatError)
+    Float(ParseFloatError),
+    /// The number could not be parsed as a [`BigInt`](crate::bignum::BigInt) or
+    /// [`Decimal`](crate::bignum::Decimal).
+    #[cfg(feature = "bignum")]
+    BigNum(ParseBigNumError)
+}
+
+/// Errors that may occur when parsing a [`BigInt`](crate::bignum::BigInt) or
+/// [`Decimal`](crate::bignum::Decimal).
+#[cfg(feature = "bignum")]
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseBigNumError {
+    /// The value does not contain any digits.
+    Empty,
+    /// The value contains a character that is not a digit.
+    InvalidDigit,
+    /// The exponent of the decimal is not a number or too big.
+    InvalidExponent
 }
 
 /// Errors that may occur when [getting data](crate::PmlStruct::get) from a [`PmlStruct`](crate::PmlStruct).
@@ -73,3 +227,92 @@ pub enum GetError {
     /// The element could not be returned as the requested type.
     InvalidType
 }
+
+/// Errors that may occur when [merging](crate::PmlStruct::merge) two [`PmlStructs`](crate::PmlStruct).
+#[derive(Debug)]
+pub enum MergeError {
+    /// Both structs contain different values for the key, but the
+    /// [policy](crate::merge::ScalarPolicy::Error) does not allow conflicts.
+    Conflict {
+        /// The full path of the conflicting value.
+        key: String,
+    },
+    /// Two arrays could not be combined because they have different types, or because they are not
+    /// struct arrays although the [policy](crate::merge::ArrayPolicy::MergeByKey) requires that.
+    ArrayTypeMismatch {
+        /// The full path of the arrays.
+        key: String,
+    },
+    /// A struct in a struct array does not contain the key that is used to
+    /// [match structs](crate::merge::ArrayPolicy::MergeByKey).
+    MissingMergeKey {
+        /// The full path of the struct.
+        key: String,
+    },
+}
+
+/// Errors that may occur when [loading](crate::loader::ConfigLoader::load) a configuration.
+#[derive(Debug)]
+pub enum LoadError {
+    /// A layer could not be read or parsed.
+    Parse {
+        /// The layer that caused the error.
+        layer: Layer,
+        /// The error that occured while parsing the layer.
+        error: ParseError,
+    },
+    /// A command-line override is not in the form `key=value`.
+    InvalidOverride(String),
+    /// An override can not be converted to the type of the value it replaces.
+    OverrideTypeMismatch {
+        /// The layer of the override.
+        layer: Layer,
+        /// The type of the value it replaces.
+        expected: String,
+    },
+    /// A layer could not be merged into the previous ones.
+    Merge(MergeError),
+}
+
+/// Errors that may occur when reading or [applying](crate::PmlStruct::apply) a
+/// [`Patch`](crate::patch::Patch).
+#[derive(Debug)]
+pub enum PatchError {
+    /// The operation with the provided index does not describe a valid operation.
+    InvalidOperation {
+        /// The index of the operation in the patch.
+        index: usize,
+    },
+    /// An operation could not be applied.
+    Failed {
+        /// The index of the operation in the patch.
+        index: usize,
+        /// The operation that could not be applied.
+        operation: Box<Operation>,
+        /// The reason the operation failed.
+        reason: PatchFailure,
+    },
+}
+
+/// The reasons an operation of a [`Patch`](crate::patch::Patch) may fail.
+#[derive(Debug)]
+pub enum PatchFailure {
+    /// The path is not valid.
+    InvalidPath,
+    /// The value at the path does not exist.
+    NotFound,
+    /// The path uses a key on an element that is not a struct or a map.
+    NotAStruct,
+    /// The path uses an index on an element that is not an array, or an insertion does not
+    /// target an array.
+    NotAnArray,
+    /// The index in the path is out of bounds.
+    IndexOutOfBounds,
+    /// The value does not have the type of the array.
+    TypeMismatch,
+    /// The precondition of the operation does not hold.
+    PreconditionFailed {
+        /// The current value, if it exists.
+        actual: Option<Element>,
+    },
+}
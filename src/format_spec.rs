@@ -0,0 +1,106 @@
+use crate::Element;
+
+/// Formats the element with the spec of an interpolation like `|port:05|`. Returns `None` if the
+/// spec is not valid for the type of the element.
+///
+/// Strings, chars and enums take `upper` or `lower`. Numbers take an optional width, which pads
+/// with zeros if it starts with `0`, followed by `.precision` for floats or a radix (`x`, `X`,
+/// `o`, `b`) for integers.
+pub(crate) fn format_element(elem: &Element, spec: &str) -> Option<String> {
+    match spec {
+        "upper" => return text(elem).map(|s| s.to_uppercase()),
+        "lower" => return text(elem).map(|s| s.to_lowercase()),
+        _ => ()
+    }
+    let zero = spec.len() > 1 && spec.starts_with('0');
+    let spec = if zero { &spec[1..] } else { spec };
+    let (width, spec) = split_number(spec);
+    let (precision, radix) = match spec.strip_prefix('.') {
+        Some(spec) => match split_number(spec) {
+            (Some(precision), radix) => (Some(precision), radix),
+            (None, _) => return None
+        }
+        None => (None, spec)
+    };
+    let value = match elem {
+        Element::PmlF32(n) if radix.is_empty() => float(n, precision),
+        Element::PmlF64(n) if radix.is_empty() => float(n, precision),
+        elem if precision.is_none() => integer(elem, radix)?,
+        _ => return None
+    };
+    let padding = width.unwrap_or(0).saturating_sub(value.chars().count());
+    Some(match (zero, value.strip_prefix('-')) {
+        (true, Some(digits)) => format!("-{}{digits}", "0".repeat(padding)),
+        (true, None) => format!("{}{value}", "0".repeat(padding)),
+        (false, _) => format!("{}{value}", " ".repeat(padding))
+    })
+}
+
+fn text(elem: &Element) -> Option<String> {
+    match elem {
+        Element::PmlString(_) | Element::PmlChar(_) | Element::PmlEnum(_) => Some(elem.to_string()),
+        _ => None
+    }
+}
+
+fn float<T: std::fmt::Display>(n: &T, precision: Option<usize>) -> String {
+    match precision {
+        Some(precision) => format!("{n:.precision$}"),
+        None => n.to_string()
+    }
+}
+
+/// Formats an integer in the radix. Negative numbers are written in two's complement, like Rust
+/// does.
+fn integer(elem: &Element, radix: &str) -> Option<String> {
+    macro_rules! radix {
+        ($($variant:ident),*) => {
+            match elem {
+                $(Element::$variant(n) => match radix {
+                    "" => Some(n.to_string()),
+                    "x" => Some(format!("{n:x}")),
+                    "X" => Some(format!("{n:X}")),
+                    "o" => Some(format!("{n:o}")),
+                    "b" => Some(format!("{n:b}")),
+                    _ => None
+                },)*
+                #[cfg(feature = "bignum")]
+                Element::PmlBigInt(_) | Element::PmlDecimal(_) if radix.is_empty() => Some(elem.to_string()),
+                _ => None
+            }
+        }
+    }
+    radix!(PmlI8, PmlI16, PmlI32, PmlI64, PmlI128, PmlU8, PmlU16, PmlU32, PmlU64, PmlU128)
+}
+
+/// Splits the leading digits from the spec.
+fn split_number(spec: &str) -> (Option<usize>, &str) {
+    let end = spec.find(|c: char| !c.is_ascii_digit()).unwrap_or(spec.len());
+    (spec[..end].parse().ok(), &spec[end..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_element;
+    use crate::Element;
+
+    #[test]
+    fn formats_valid_specs() {
+        assert_eq!(format_element(&Element::PmlU16(42), "05").as_deref(), Some("00042"));
+        assert_eq!(format_element(&Element::PmlI8(-5), "04").as_deref(), Some("-005"));
+        assert_eq!(format_element(&Element::PmlU8(255), "x").as_deref(), Some("ff"));
+        assert_eq!(format_element(&Element::PmlF64(1.5), "6.2").as_deref(), Some("  1.50"));
+        assert_eq!(format_element(&Element::PmlString(String::from("ab")), "upper").as_deref(), Some("AB"));
+    }
+
+    #[test]
+    fn rejects_invalid_specs() {
+        assert_eq!(format_element(&Element::PmlU8(1), "y"), None);
+        assert_eq!(format_element(&Element::PmlU8(1), ".2"), None);
+        assert_eq!(format_element(&Element::PmlF32(1.0), "x"), None);
+        assert_eq!(format_element(&Element::PmlF64(1.0), ".x"), None);
+        assert_eq!(format_element(&Element::PmlU8(1), "upper"), None);
+        assert_eq!(format_element(&Element::PmlString(String::from("a")), "05"), None);
+        assert_eq!(format_element(&Element::PmlBool(true), ""), None);
+    }
+}
@@ -0,0 +1,318 @@
+//! Functions that can be called in PML values, like `name = upper(|user|);`.
+//!
+//! Calls may be part of expressions like `len(|hosts|) * 2` and are evaluated once the values of
+//! their arguments are resolved. [`Functions::new`] contains the built-in functions:
+//!
+//! - `upper(str)`, `lower(str)` and `trim(str)` change strings.
+//! - `replace(str, from, to)` replaces all occurrences of `from`.
+//! - `join(array, separator)` joins the values of an array.
+//! - `len(value)` returns the number of chars of a string, or the length of an array, map or
+//!   byte string.
+//! - `min(...)` and `max(...)` return the smallest or biggest of their arguments, or of the
+//!   values of a single array.
+//!
+//! Two more built-in functions have to be added explicitly:
+//!
+//! - `env(name)` returns the value of an environment variable, `env(name, default)` returns the
+//!   default if it is not set. It is added with [`with_env`](Functions::with_env).
+//! - `file(path)` returns the content of a file. Relative paths are resolved against the
+//!   directory of the parsed file, or the working directory if the input is not a file. It is
+//!   added with [`with_file`](Functions::with_file).
+//!
+//! Custom functions are added with [`register`](Functions::register). Sets of functions other than
+//! the default one are used with
+//! [`parse::string_with_functions`](crate::parse::string_with_functions) or
+//! [`parse::file_with_functions`](crate::parse::file_with_functions).
+//!
+//! # Security
+//! `env` and `file` let a PML file read any environment variable and any file the process has
+//! access to, which may contain secrets. They should only be added for input that is trusted.
+
+use std::{collections::HashMap, env, fmt::{Debug, Formatter, Result as FmtResult}, fs, path::{Path, PathBuf}, sync::Arc};
+use crate::{Element, parse::{integer, is_less}};
+
+pub(crate) type Function = Arc<dyn Fn(&[Element]) -> Result<Element, String> + Send + Sync>;
+
+/// The type of a parameter of a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamType {
+    /// Accepts values of any type.
+    Any,
+    /// Accepts strings.
+    Str,
+    /// Accepts integers of any size.
+    Int,
+    /// Accepts integers and floats.
+    Number,
+    /// Accepts bools.
+    Bool,
+    /// Accepts arrays.
+    Array,
+}
+
+/// The types of the parameters of a function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    params: Vec<ParamType>,
+    rest: Option<ParamType>,
+}
+
+/// A set of functions that can be called while parsing.
+#[derive(Clone)]
+pub struct Functions {
+    functions: HashMap<String, (Signature, Function)>,
+    /// Whether `file` is the built-in function, which is replaced to resolve relative paths
+    /// against the directory of the parsed file.
+    builtin_file: bool,
+}
+
+impl ParamType {
+    fn accepts(self, elem: &Element) -> bool {
+        match self {
+            ParamType::Any => true,
+            ParamType::Str => matches!(elem, Element::PmlString(_)),
+            ParamType::Int => is_int(elem),
+            ParamType::Number => is_int(elem) || matches!(elem, Element::PmlF32(_) | Element::PmlF64(_)),
+            ParamType::Bool => matches!(elem, Element::PmlBool(_)),
+            ParamType::Array => matches!(elem, Element::PmlArray(_)),
+        }
+    }
+}
+
+impl Signature {
+    /// Creates a signature with the provided parameters.
+    pub fn new(params: &[ParamType]) -> Self {
+        Self{params: params.to_vec(), rest: None}
+    }
+
+    /// Allows any number of additional arguments of the provided type after the parameters.
+    #[must_use]
+    pub fn rest(mut self, param: ParamType) -> Self {
+        self.rest = Some(param);
+        self
+    }
+
+    /// Checks if the arguments match the signature.
+    pub(crate) fn accepts(&self, args: &[Element]) -> bool {
+        if args.len() < self.params.len() || (self.rest.is_none() && args.len() > self.params.len()) {
+            return false;
+        }
+        args.iter().enumerate().all(|(i, arg)| match self.params.get(i).copied().or(self.rest) {
+            Some(param) => param.accepts(arg),
+            None => false
+        })
+    }
+}
+
+impl Functions {
+    /// Creates a set that contains the built-in functions, except for `env` and `file`.
+    pub fn new() -> Self {
+        use ParamType::{Any, Str, Array};
+        Self::empty()
+            .register("upper", Signature::new(&[Str]), |args| Ok(Element::PmlString(string(&args[0]).to_uppercase())))
+            .register("lower", Signature::new(&[Str]), |args| Ok(Element::PmlString(string(&args[0]).to_lowercase())))
+            .register("trim", Signature::new(&[Str]), |args| Ok(Element::PmlString(string(&args[0]).trim().to_string())))
+            .register("replace", Signature::new(&[Str, Str, Str]), |args| Ok(Element::PmlString(string(&args[0]).replace(string(&args[1]), string(&args[2])))))
+            .register("join", Signature::new(&[Array, Str]), |args| {
+                let values: Vec<String> = array(&args[0]).iter().map(ToString::to_string).collect();
+                Ok(Element::PmlString(values.join(string(&args[1]))))
+            })
+            .register("len", Signature::new(&[Any]), |args| {
+                let len = match &args[0] {
+                    Element::PmlString(s) => s.chars().count(),
+                    Element::PmlArray(arr) => arr.len(),
+                    Element::PmlMap(map) => map.len(),
+                    Element::PmlBytes(bytes) => bytes.len(),
+                    elem => return Err(format!("{} has no length", elem.type_name()))
+                };
+                Ok(integer(i128::try_from(len).map_err(|e| e.to_string())?))
+            })
+            .register("min", Signature::new(&[Any]).rest(Any), |args| extreme(args, is_less))
+            .register("max", Signature::new(&[Any]).rest(Any), |args| extreme(args, |a, b| is_less(b, a)))
+    }
+
+    /// Adds the built-in `env` function, which reads environment variables. See the
+    /// [security notes](crate::functions#security).
+    #[must_use]
+    pub fn with_env(self) -> Self {
+        self.register("env", Signature::new(&[ParamType::Str]).rest(ParamType::Str), |args| {
+            if args.len() > 2 {
+                return Err(String::from("env takes at most two arguments"));
+            }
+            match (env::var(string(&args[0])), args.get(1)) {
+                (Ok(value), _) => Ok(Element::PmlString(value)),
+                (Err(_), Some(default)) => Ok(default.clone()),
+                (Err(e), None) => Err(format!("{}: {e}", string(&args[0])))
+            }
+        })
+    }
+
+    /// Adds the built-in `file` function, which reads files. See the
+    /// [security notes](crate::functions#security).
+    #[must_use]
+    pub fn with_file(self) -> Self {
+        let mut functions = self.register("file", Signature::new(&[ParamType::Str]), read_file(None));
+        functions.builtin_file = true;
+        functions
+    }
+
+    /// Creates a set without any functions.
+    pub fn empty() -> Self {
+        Self{functions: HashMap::new(), builtin_file: false}
+    }
+
+    /// Removes the function with the provided name, if there is one.
+    #[must_use]
+    pub fn without(mut self, name: &str) -> Self {
+        self.functions.remove(name);
+        self.builtin_file &= name != "file";
+        self
+    }
+
+    /// Returns the functions for parsing a file in the directory, whose built-in `file` function
+    /// resolves relative paths against the directory.
+    pub(crate) fn in_dir(&self, dir: &Path) -> Self {
+        let mut functions = self.clone();
+        if self.builtin_file {
+            functions.functions.insert(String::from("file"), (Signature::new(&[ParamType::Str]), Arc::new(read_file(Some(dir.to_path_buf())))));
+        }
+        functions
+    }
+
+    /// Adds a function, or replaces the function with the same name.
+    ///
+    /// The function is only called with arguments that match the signature. If it returns an
+    /// error, parsing fails with [`FunctionFailed`](crate::ParseError::FunctionFailed).
+    #[must_use]
+    pub fn register<F>(mut self, name: &str, signature: Signature, function: F) -> Self
+        where F: Fn(&[Element]) -> Result<Element, String> + Send + Sync + 'static
+        {
+            self.functions.insert(name.to_string(), (signature, Arc::new(function)));
+            self.builtin_file &= name != "file";
+            self
+        }
+
+    pub(crate) fn get(&self, name: &str) -> Option<(Signature, Function)> {
+        self.functions.get(name).cloned()
+    }
+}
+
+impl Default for Functions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for Functions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let mut names: Vec<&String> = self.functions.keys().collect();
+        names.sort();
+        f.debug_struct("Functions").field("functions", &names).finish()
+    }
+}
+
+/// Returns the built-in `file` function, which resolves relative paths against the directory.
+fn read_file(dir: Option<PathBuf>) -> impl Fn(&[Element]) -> Result<Element, String> + Send + Sync {
+    move |args| {
+        let path = match &dir {
+            Some(dir) => dir.join(string(&args[0])),
+            None => PathBuf::from(string(&args[0]))
+        };
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(Element::PmlString(content)),
+            Err(e) => Err(format!("{}: {e}", string(&args[0])))
+        }
+    }
+}
+
+fn is_int(elem: &Element) -> bool {
+    matches!(elem, Element::PmlI8(_) | Element::PmlI16(_) | Element::PmlI32(_) | Element::PmlI64(_) | Element::PmlI128(_)
+        | Element::PmlU8(_) | Element::PmlU16(_) | Element::PmlU32(_) | Element::PmlU64(_) | Element::PmlU128(_))
+}
+
+fn string(elem: &Element) -> &str {
+    match elem {
+        Element::PmlString(s) => s,
+        _ => unreachable!("The signature should only allow strings")
+    }
+}
+
+fn array(elem: &Element) -> Vec<Element> {
+    match elem {
+        Element::PmlArray(arr) => (0..arr.len()).filter_map(|i| arr.get_element(i)).collect(),
+        _ => unreachable!("The signature should only allow arrays")
+    }
+}
+
+/// Returns the argument that is preferred over all others, or the preferred value of a single
+/// array.
+fn extreme(args: &[Element], prefer: impl Fn(&Element, &Element) -> Option<bool>) -> Result<Element, String> {
+    let values = match args {
+        [arr @ Element::PmlArray(_)] => array(arr),
+        args => args.to_vec()
+    };
+    let mut values = values.into_iter();
+    let first = values.next().ok_or_else(|| String::from("there are no values"))?;
+    values.try_fold(first, |best, value| match prefer(&value, &best) {
+        Some(true) => Ok(value),
+        Some(false) => Ok(best),
+        None => Err(format!("{} and {} can not be compared", best.type_name(), value.type_name()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Functions, ParamType, Signature};
+    use crate::{parse, loader::ConfigLoader, Element, ParseError};
+    use std::{env, fs};
+
+    fn is_send_and_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn calls_built_in_functions() {
+        let config = parse::string("name = \"  Pml \"; hosts = [\"a\", \"b\"]; u = upper(trim(|name|)); j = join(|hosts|, \", \"); n = len(|hosts|) * 2; m = max(4, 7.5, 2);").unwrap();
+        assert_eq!(config.get::<String>("u").unwrap(), "PML");
+        assert_eq!(config.get::<String>("j").unwrap(), "a, b");
+        assert_eq!(config.get::<u8>("n").unwrap(), 4);
+        assert_eq!(config.get::<f32>("m").unwrap(), 7.5);
+        assert!(matches!(parse::string("a = upper(1);"), Err(ParseError::InvalidOperands{..})));
+        assert!(matches!(parse::string("a = nope(1);"), Err(ParseError::UnknownFunction{..})));
+    }
+
+    #[test]
+    fn default_parse_rejects_env_and_file() {
+        assert!(matches!(parse::string("a = file(\"/etc/hostname\");"), Err(ParseError::UnknownFunction{ref name, ..}) if name == "file"));
+        assert!(matches!(parse::string("a = env(\"HOME\");"), Err(ParseError::UnknownFunction{ref name, ..}) if name == "env"));
+    }
+
+    #[test]
+    fn reads_env_and_files_when_added() {
+        let path = env::temp_dir().join("pml_functions_test.txt");
+        fs::write(&path, "content").unwrap();
+        env::set_var("PML_FUNCTIONS_TEST", "value");
+        let functions = Functions::new().with_env().with_file();
+        let input = format!("a = file(\"{}\"); b = env(\"PML_FUNCTIONS_TEST\"); c = env(\"PML_FUNCTIONS_TEST_MISSING\", \"default\");", path.display());
+        let config = parse::string_with_functions(&input, &functions).unwrap();
+        assert_eq!(config.get::<String>("a").unwrap(), "content");
+        assert_eq!(config.get::<String>("b").unwrap(), "value");
+        assert_eq!(config.get::<String>("c").unwrap(), "default");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn calls_registered_functions() {
+        let functions = Functions::empty().register("double", Signature::new(&[ParamType::Str]), |args| match &args[0] {
+            Element::PmlString(s) => Ok(Element::PmlString(s.repeat(2))),
+            _ => Err(String::from("not a string"))
+        });
+        let config = parse::string_with_functions("a = double(\"ab\");", &functions).unwrap();
+        assert_eq!(config.get::<String>("a").unwrap(), "abab");
+        assert!(parse::string_with_functions("a = upper(\"ab\");", &functions).is_err());
+    }
+
+    #[test]
+    fn can_be_shared_between_threads() {
+        is_send_and_sync::<Functions>();
+        is_send_and_sync::<ConfigLoader>();
+    }
+}
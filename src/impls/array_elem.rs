@@ -0,0 +1,292 @@
+use crate::{Element, ArrayElement, ArrayType, FromElement, PmlStruct, GetError};
+
+macro_rules! array_access {
+    ($($variant:ident),*) => {
+        impl ArrayElement {
+            pub(crate) fn len(&self) -> usize {
+                match self {
+                    ArrayElement::PmlStruct(a) => a.len(),
+                    ArrayElement::PmlMixed(a) | ArrayElement::PmlArray(a) => a.len(),
+                    $(ArrayElement::$variant(a) => a.len(),)*
+                }
+            }
+
+            pub(crate) fn get_element(&self, index: usize) -> Option<Element> {
+                match self {
+                    ArrayElement::PmlStruct(a) => a.get(index).cloned().map(Into::into),
+                    ArrayElement::PmlMixed(a) | ArrayElement::PmlArray(a) => a.get(index).cloned(),
+                    $(ArrayElement::$variant(a) => a.get(index).cloned().map(Element::$variant),)*
+                }
+            }
+
+            /// Replaces the value at the index and returns the old one. Returns the new value as
+            /// error if it does not have the type of the array.
+            pub(crate) fn set_element(&mut self, index: usize, value: Element) -> Result<Element, Element> {
+                match (self, value) {
+                    (ArrayElement::PmlStruct(a), Element::PmlStruct(v)) => Ok(std::mem::replace(&mut a[index], *v).into()),
+                    (ArrayElement::PmlMixed(a), v) => Ok(std::mem::replace(&mut a[index], v)),
+                    (ArrayElement::PmlArray(a), v) if fits_nested(a, Some(index), &v) => Ok(std::mem::replace(&mut a[index], v)),
+                    $((ArrayElement::$variant(a), Element::$variant(v)) => Ok(Element::$variant(std::mem::replace(&mut a[index], v))),)*
+                    (_, value) => Err(value)
+                }
+            }
+
+            /// Inserts the value at the index. Returns the value as error if it does not have the
+            /// type of the array.
+            pub(crate) fn insert_element(&mut self, index: usize, value: Element) -> Result<(), Element> {
+                match (self, value) {
+                    (ArrayElement::PmlStruct(a), Element::PmlStruct(v)) => a.insert(index, *v),
+                    (ArrayElement::PmlMixed(a), v) => a.insert(index, v),
+                    (ArrayElement::PmlArray(a), v) if fits_nested(a, None, &v) => a.insert(index, v),
+                    $((ArrayElement::$variant(a), Element::$variant(v)) => a.insert(index, v),)*
+                    (_, value) => return Err(value)
+                }
+                Ok(())
+            }
+
+            pub(crate) fn remove_element(&mut self, index: usize) -> Element {
+                match self {
+                    ArrayElement::PmlStruct(a) => a.remove(index).into(),
+                    ArrayElement::PmlMixed(a) | ArrayElement::PmlArray(a) => a.remove(index),
+                    $(ArrayElement::$variant(a) => Element::$variant(a.remove(index)),)*
+                }
+            }
+        }
+    }
+}
+
+/// Checks if the value is an array with the type of the other arrays in a nested array, ignoring
+/// the one at index `skip`.
+fn fits_nested(arrays: &[Element], skip: Option<usize>, value: &Element) -> bool {
+    matches!(value, Element::PmlArray(_)) && arrays.iter().enumerate()
+        .filter(|(i, _)| Some(*i) != skip)
+        .all(|(_, e)| e.type_name() == value.type_name())
+}
+
+array_access!(PmlBool, PmlString, PmlF32, PmlF64, PmlI8, PmlI16, PmlI32, PmlI64, PmlI128, PmlU8, PmlU16, PmlU32, PmlU64, PmlU128);
+
+macro_rules! array_type {
+    ($($type:ty: $variant:ident),*) => {
+        $(
+            impl ArrayType for $type {
+                fn from_array(arr: &ArrayElement) -> Option<&[Self]> {
+                    match arr {
+                        ArrayElement::$variant(a) => Some(a),
+                        _ => None
+                    }
+                }
+            }
+        )*
+    }
+}
+
+array_type!(bool: PmlBool, String: PmlString, PmlStruct: PmlStruct, f32: PmlF32, f64: PmlF64, i8: PmlI8, i16: PmlI16, i32: PmlI32, i64: PmlI64, i128: PmlI128, u8: PmlU8, u16: PmlU16, u32: PmlU32, u64: PmlU64, u128: PmlU128);
+
+impl ArrayType for Element {
+    fn from_array(arr: &ArrayElement) -> Option<&[Self]> {
+        match arr {
+            ArrayElement::PmlMixed(a) | ArrayElement::PmlArray(a) => Some(a),
+            _ => None
+        }
+    }
+}
+
+/// A number that is part of an array whose type is inferred, with the number of bits of its type.
+enum InferredNumber {
+    Unsigned(u128, u32),
+    Signed(i128, u32),
+    F32(f32),
+    F64(f64),
+}
+
+impl InferredNumber {
+    fn from_element(elem: &Element) -> Option<Self> {
+        Some(match elem {
+            Element::PmlU8(n) => Self::Unsigned(u128::from(*n), u8::BITS),
+            Element::PmlU16(n) => Self::Unsigned(u128::from(*n), u16::BITS),
+            Element::PmlU32(n) => Self::Unsigned(u128::from(*n), u32::BITS),
+            Element::PmlU64(n) => Self::Unsigned(u128::from(*n), u64::BITS),
+            Element::PmlU128(n) => Self::Unsigned(*n, u128::BITS),
+            Element::PmlI8(n) => Self::Signed(i128::from(*n), i8::BITS),
+            Element::PmlI16(n) => Self::Signed(i128::from(*n), i16::BITS),
+            Element::PmlI32(n) => Self::Signed(i128::from(*n), i32::BITS),
+            Element::PmlI64(n) => Self::Signed(i128::from(*n), i64::BITS),
+            Element::PmlI128(n) => Self::Signed(*n, i128::BITS),
+            Element::PmlF32(n) => Self::F32(*n),
+            Element::PmlF64(n) => Self::F64(*n),
+            _ => return None
+        })
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn as_f64(&self) -> f64 {
+        match self {
+            Self::Unsigned(n, _) => *n as f64,
+            Self::Signed(n, _) => *n as f64,
+            Self::F32(n) => f64::from(*n),
+            Self::F64(n) => *n
+        }
+    }
+
+    /// Returns the value as a signed number and the number of bits its signed type needs at least.
+    fn as_signed(&self) -> Option<(i128, u32)> {
+        match self {
+            Self::Signed(n, bits) => Some((*n, *bits)),
+            Self::Unsigned(n, bits) => {
+                let n = i128::try_from(*n).ok()?;
+                let needed = [i8::BITS, i16::BITS, i32::BITS, i64::BITS].into_iter().find(|b| n < 1 << (b - 1)).unwrap_or(i128::BITS);
+                Some((n, needed.max(*bits)))
+            }
+            _ => None
+        }
+    }
+}
+
+impl ArrayElement {
+    /// Creates an array from the values, using the smallest type that fits all of them and is at
+    /// least as big as each of their types. Arrays of the same type result in a `PmlArray`. Values
+    /// with different types that can not be unified result in a `PmlMixed` array.
+    #[allow(clippy::cast_possible_truncation)]
+    pub(crate) fn infer(values: Vec<Element>) -> Self {
+        if values.is_empty() {
+            return ArrayElement::PmlMixed(values);
+        }
+        if values.iter().all(|v| matches!(v, Element::PmlBool(_))) {
+            return ArrayElement::PmlBool(values.iter().filter_map(|v| bool::try_from(v).ok()).collect());
+        }
+        if values.iter().all(|v| matches!(v, Element::PmlString(_))) {
+            return ArrayElement::PmlString(values.into_iter().filter_map(|v| match v { Element::PmlString(s) => Some(s), _ => None }).collect());
+        }
+        if values.iter().all(|v| matches!(v, Element::PmlStruct(_))) {
+            return ArrayElement::PmlStruct(values.into_iter().filter_map(|v| match v { Element::PmlStruct(s) => Some(*s), _ => None }).collect::<Vec<PmlStruct>>());
+        }
+        if values.iter().all(|v| matches!(v, Element::PmlArray(_)) && v.type_name() == values[0].type_name()) {
+            return ArrayElement::PmlArray(values);
+        }
+        let Some(numbers) = values.iter().map(InferredNumber::from_element).collect::<Option<Vec<_>>>() else {
+            return ArrayElement::PmlMixed(values);
+        };
+        if numbers.iter().any(|n| matches!(n, InferredNumber::F64(_))) {
+            return ArrayElement::PmlF64(numbers.iter().map(InferredNumber::as_f64).collect());
+        }
+        if numbers.iter().any(|n| matches!(n, InferredNumber::F32(_))) {
+            return ArrayElement::PmlF32(numbers.iter().map(|n| n.as_f64() as f32).collect());
+        }
+        if numbers.iter().all(|n| matches!(n, InferredNumber::Unsigned(..))) {
+            let bits = numbers.iter().map(|n| match n { InferredNumber::Unsigned(_, bits) => *bits, _ => 0 }).max().unwrap_or_default();
+            let unsigned = numbers.into_iter().filter_map(|n| match n { InferredNumber::Unsigned(n, _) => Some(n), _ => None });
+            return match bits {
+                8 => ArrayElement::PmlU8(unsigned.map(|n| n as u8).collect()),
+                16 => ArrayElement::PmlU16(unsigned.map(|n| n as u16).collect()),
+                32 => ArrayElement::PmlU32(unsigned.map(|n| n as u32).collect()),
+                64 => ArrayElement::PmlU64(unsigned.map(|n| n as u64).collect()),
+                _ => ArrayElement::PmlU128(unsigned.collect())
+            };
+        }
+        let Some(signed) = numbers.iter().map(InferredNumber::as_signed).collect::<Option<Vec<_>>>() else {
+            return ArrayElement::PmlMixed(values);
+        };
+        let bits = signed.iter().map(|(_, bits)| *bits).max().unwrap_or_default();
+        let signed = signed.into_iter().map(|(n, _)| n);
+        match bits {
+            8 => ArrayElement::PmlI8(signed.map(|n| n as i8).collect()),
+            16 => ArrayElement::PmlI16(signed.map(|n| n as i16).collect()),
+            32 => ArrayElement::PmlI32(signed.map(|n| n as i32).collect()),
+            64 => ArrayElement::PmlI64(signed.map(|n| n as i64).collect()),
+            _ => ArrayElement::PmlI128(signed.collect())
+        }
+    }
+}
+
+impl TryFrom<&Element> for Vec<Element> {
+    type Error = GetError;
+    fn try_from(value: &Element) -> Result<Self, Self::Error> {
+        match value {
+            Element::PmlArray(ArrayElement::PmlMixed(arr) | ArrayElement::PmlArray(arr)) => Ok(arr.clone()),
+            _ => Err(GetError::InvalidType)
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a Element> for &'a Vec<Element> {
+    type Error = GetError;
+    fn try_from(value: &'a Element) -> Result<Self, Self::Error> {
+        match value {
+            Element::PmlArray(ArrayElement::PmlMixed(arr) | ArrayElement::PmlArray(arr)) => Ok(arr),
+            _ => Err(GetError::InvalidType)
+        }
+    }
+}
+
+impl<'a, T> TryFrom<&'a Element> for Vec<Vec<T>>
+    where
+    Vec<T>: TryFrom<&'a Element, Error = GetError>
+    {
+        type Error = GetError;
+        fn try_from(value: &'a Element) -> Result<Self, Self::Error> {
+            match value {
+                Element::PmlArray(ArrayElement::PmlArray(arr) | ArrayElement::PmlMixed(arr)) => arr.iter().map(Vec::<T>::try_from).collect(),
+                _ => Err(GetError::InvalidType)
+            }
+        }
+    }
+
+impl<'a, T> FromElement<'a> for Vec<Vec<T>>
+    where
+    Vec<T>: TryFrom<&'a Element, Error = GetError>
+    {
+        fn from_element(elem: &'a Element) -> Result<Self, GetError> {
+            Self::try_from(elem)
+        }
+    }
+
+impl<'a, T> TryFrom<&'a Element> for Option<Vec<Vec<T>>>
+    where
+    Vec<T>: TryFrom<&'a Element, Error = GetError>
+    {
+        type Error = GetError;
+        fn try_from(value: &'a Element) -> Result<Self, Self::Error> {
+            match value {
+                Element::PmlNull => Ok(None),
+                value => Vec::<Vec<T>>::try_from(value).map(Some)
+            }
+        }
+    }
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::string;
+    use crate::PmlStruct;
+
+    #[test]
+    fn converts_nested_arrays() {
+        let config = string("grid = <u8> [[1, 2], [3, 4,], []]; deep = <i16> [[[1], [2, -3]], [[4]]]; names = <str> [[\"a\"], []];").unwrap();
+        assert_eq!(config.get::<Vec<Vec<u8>>>("grid").unwrap(), vec![vec![1, 2], vec![3, 4], vec![]]);
+        assert_eq!(config.get::<Vec<Vec<Vec<i16>>>>("deep").unwrap(), vec![vec![vec![1], vec![2, -3]], vec![vec![4]]]);
+        assert_eq!(config.get::<Vec<Vec<String>>>("names").unwrap(), vec![vec![String::from("a")], vec![]]);
+        assert!(config.get::<Vec<Vec<i8>>>("grid").is_err());
+    }
+
+    #[test]
+    fn indexes_into_nested_arrays() {
+        let config = string("grid = <u8> [[1, 2], [3]]; rows = [[1, 2], [\"x\"]]; servers = [{port = 80; tags = [\"a\", \"b\"];}];").unwrap();
+        assert_eq!(config.get::<Vec<u8>>("grid[1]").unwrap(), vec![3]);
+        assert_eq!(config.get::<u8>("grid[0][1]").unwrap(), 2);
+        assert_eq!(config.get::<String>("rows[1][0]").unwrap(), "x");
+        assert_eq!(config.get::<u32>("servers[0].port").unwrap(), 80);
+        assert_eq!(config.get::<String>("servers[0].tags[1]").unwrap(), "b");
+        assert!(config.get::<u8>("grid[2]").is_err());
+    }
+
+    #[test]
+    fn borrows_arrays_and_struct_arrays() {
+        let config = string("a = <u8> [1, 2]; b = []; c = [{x = 1;}, {x = 2;}]; d = <u8> [[1], [2, 3]];").unwrap();
+        assert_eq!(config.get_array::<u8>("a").unwrap(), &[1, 2]);
+        assert!(config.get_array::<u8>("b").unwrap().is_empty());
+        assert!(config.get_array::<String>("a").is_err());
+        let structs: &[PmlStruct] = config.get_structs("c").unwrap();
+        assert_eq!(structs.len(), 2);
+        assert_eq!(structs[1].get::<u32>("x").unwrap(), 2);
+        assert_eq!(config.get_array::<u8>("d[1]").unwrap(), &[2, 3]);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::errors::{ParseError, ParseNumberError};
+use crate::errors::{ParseError, ParseNumberError, LoadError, MergeError};
 use std::num::{ParseIntError, ParseFloatError};
 
 impl From<std::io::Error> for ParseError {
@@ -17,3 +17,16 @@ impl From<ParseFloatError> for ParseNumberError {
         ParseNumberError::Float(e)
     }
 }
+
+#[cfg(feature = "bignum")]
+impl From<crate::errors::ParseBigNumError> for ParseNumberError {
+    fn from(e: crate::errors::ParseBigNumError) -> Self {
+        ParseNumberError::BigNum(e)
+    }
+}
+
+impl From<MergeError> for LoadError {
+    fn from(e: MergeError) -> Self {
+        LoadError::Merge(e)
+    }
+}
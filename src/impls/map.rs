@@ -0,0 +1,96 @@
+use std::collections::{BTreeMap, HashMap};
+use crate::{Element, FromElement, GetError};
+
+impl From<BTreeMap<String, Element>> for Element {
+    fn from(map: BTreeMap<String, Element>) -> Self {
+        Element::PmlMap(map)
+    }
+}
+
+impl<'a> TryFrom<&'a Element> for &'a BTreeMap<String, Element> {
+    type Error = GetError;
+    fn try_from(value: &'a Element) -> Result<Self, Self::Error> {
+        match value {
+            Element::PmlMap(map) => Ok(map),
+            _ => Err(GetError::InvalidType)
+        }
+    }
+}
+
+macro_rules! map_conversion {
+    ($($map:ident),*) => {
+        $(
+            impl<'a, T> TryFrom<&'a Element> for $map<String, T>
+                where
+                T: TryFrom<&'a Element, Error = GetError>
+                {
+                    type Error = GetError;
+                    fn try_from(value: &'a Element) -> Result<Self, Self::Error> {
+                        match value {
+                            Element::PmlMap(map) => map.iter().map(|(k, v)| Ok((k.clone(), T::try_from(v)?))).collect(),
+                            _ => Err(GetError::InvalidType)
+                        }
+                    }
+                }
+
+            impl<'a, T> FromElement<'a> for $map<String, T>
+                where
+                T: TryFrom<&'a Element, Error = GetError>
+                {
+                    fn from_element(elem: &'a Element) -> Result<Self, GetError> {
+                        Self::try_from(elem)
+                    }
+                }
+
+            impl<'a, T> TryFrom<&'a Element> for Option<$map<String, T>>
+                where
+                T: TryFrom<&'a Element, Error = GetError>
+                {
+                    type Error = GetError;
+                    fn try_from(value: &'a Element) -> Result<Self, Self::Error> {
+                        match value {
+                            Element::PmlNull => Ok(None),
+                            value => $map::<String, T>::try_from(value).map(Some)
+                        }
+                    }
+                }
+        )*
+    }
+}
+
+map_conversion!(HashMap, BTreeMap);
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+    use crate::{parse, ParseError};
+
+    #[test]
+    fn reads_maps_with_quoted_keys() {
+        let config = parse::string("hosts = <map<u16>> { \"example.com\" = 443; \"a\\\"b\" = 80; local = 8080; };").unwrap();
+        let hosts = config.get::<HashMap<String, u16>>("hosts").unwrap();
+        assert_eq!(hosts["example.com"], 443);
+        assert_eq!(hosts["a\"b"], 80);
+        assert_eq!(hosts["local"], 8080);
+        assert!(config.get::<BTreeMap<String, u16>>("hosts").is_ok());
+        assert!(config.get::<HashMap<String, bool>>("hosts").is_err());
+    }
+
+    #[test]
+    fn forces_the_type_of_values() {
+        assert!(parse::string("m = <map<u8>> { \"a\" = 256; };").is_err());
+        assert!(parse::string("m = <map<u8>> { \"a\" = null; };").is_err());
+        let config = parse::string("m = <map<u8?>> { \"a\" = null; \"b\" = 2; }; n = <map<u8>?> null;").unwrap();
+        let map = config.get::<BTreeMap<String, Option<u8>>>("m").unwrap();
+        assert_eq!(map["a"], None);
+        assert_eq!(map["b"], Some(2));
+        assert_eq!(config.get::<Option<HashMap<String, u8>>>("n").unwrap(), None);
+        assert!(matches!(parse::string("m = <map<nope>> {};"), Err(ParseError::UnknownForcedType{..})));
+    }
+
+    #[test]
+    fn serializes_maps() {
+        let config = parse::string("m = <map<str>> { \"x.y\" = \"a\"; z = \"b\"; };").unwrap();
+        assert_eq!(parse::string(&config.to_pml_string()).unwrap(), config);
+    }
+}
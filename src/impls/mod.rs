@@ -1,6 +1,34 @@
-use crate::{elem::{Element, ArrayElement}, PmlStruct, GetError};
+use crate::{elem::{Element, ArrayElement}, PmlStruct, GetError, FromElement, datetime::{Date, Time, DateTime, LocalDateTime}, enums::EnumValue};
+use std::{collections::BTreeMap, time::{Duration, SystemTime}};
+#[cfg(feature = "bignum")]
+use crate::bignum::{BigInt, Decimal};
+/// Implements [`FromElement`] with the `TryFrom` conversions. The types before the semicolon do
+/// not borrow from the element, so they can also be converted from copies.
+macro_rules! from_element {
+    ($($owned:ty),*; $($borrowed:ty),*) => {
+        $(
+            impl<'a> FromElement<'a> for $owned {
+                fn from_element(elem: &'a Element) -> Result<Self, GetError> {
+                    Self::try_from(elem)
+                }
+
+                fn from_copy(elem: Element) -> Result<Self, GetError> {
+                    Self::try_from(&elem)
+                }
+            }
+        )*
+        $(
+            impl<'a> FromElement<'a> for $borrowed {
+                fn from_element(elem: &'a Element) -> Result<Self, GetError> {
+                    Self::try_from(elem)
+                }
+            }
+        )*
+    }
+}
+
 macro_rules! add_primitive {
-    ($pml_elem:ident, $type:ty $(,$casts:ident)*) => {
+    ($pml_elem:ident, $type:ty $(,$casts:ident)* $(; $scalar_casts:ident)* $(| $vec_casts:ident)*) => {
         impl From<$type> for Element {
             fn from(f: $type) -> Self {
                 Element::$pml_elem(f)
@@ -15,6 +43,9 @@ macro_rules! add_primitive {
                     $(
                         Element::$casts(e) => Ok(*e as $type),
                     )*
+                    $(
+                        Element::$scalar_casts(e) => Ok(*e as $type),
+                    )*
                     _ => Err(Self::Error::InvalidType)
                 }
             }
@@ -40,10 +71,14 @@ macro_rules! add_primitive {
             type Error = GetError;
             fn try_from(elem: &Element) -> Result<Self, Self::Error> {
                 match elem {
+                    Element::PmlArray(ArrayElement::PmlMixed(e)) if e.is_empty() => Ok(Vec::new()),
                     Element::PmlArray(ArrayElement::$pml_elem(e)) => Ok(e.clone()),
                     $(
                         Element::PmlArray(ArrayElement::$casts(e)) => Ok(e.iter().map(|n| *n as $type).collect()),
                     )*
+                    $(
+                        Element::$vec_casts(e) => Ok(e.clone()),
+                    )*
                     _ => Err(Self::Error::InvalidType)
                 }
             }
@@ -52,12 +87,19 @@ macro_rules! add_primitive {
         impl<'a> TryFrom<&'a Element> for &'a Vec<$type> {
             type Error = GetError;
             fn try_from(elem: &'a Element) -> Result<Self, Self::Error> {
+                const EMPTY: &Vec<$type> = &Vec::new();
                 match elem {
+                    Element::PmlArray(ArrayElement::PmlMixed(e)) if e.is_empty() => Ok(EMPTY),
                     Element::PmlArray(ArrayElement::$pml_elem(e)) => Ok(e),
+                    $(
+                        Element::$vec_casts(e) => Ok(e),
+                    )*
                     _ => Err(Self::Error::InvalidType)
                 }
             }
         }
+
+        from_element!($type, Vec<$type>; &'a $type, &'a Vec<$type>);
     }
 }
 
@@ -101,6 +143,77 @@ macro_rules! generic_non_primitive {
                 }
             }
         }
+
+        from_element!(; &'a Vec<$type>);
+    }
+}
+
+/// Implements conversions for values that can not be part of arrays with a single type. Arrays of
+/// them are `PmlMixed` arrays.
+macro_rules! add_scalar {
+    ($pml_elem:ident, $type:ty) => {
+        impl From<$type> for Element {
+            fn from(f: $type) -> Self {
+                Element::$pml_elem(f)
+            }
+        }
+
+        impl TryFrom<&Element> for $type {
+            type Error = GetError;
+            fn try_from(elem: &Element) -> Result<Self, Self::Error> {
+                match elem {
+                    Element::$pml_elem(e) => Ok(e.clone()),
+                    _ => Err(Self::Error::InvalidType)
+                }
+            }
+        }
+
+        impl<'a> TryFrom<&'a Element> for &'a $type {
+            type Error = GetError;
+            fn try_from(elem: &'a Element) -> Result<Self, Self::Error> {
+                match elem {
+                    Element::$pml_elem(e) => Ok(e),
+                    _ => Err(Self::Error::InvalidType)
+                }
+            }
+        }
+
+        impl TryFrom<&Element> for Vec<$type> {
+            type Error = GetError;
+            fn try_from(elem: &Element) -> Result<Self, Self::Error> {
+                match elem {
+                    Element::PmlArray(ArrayElement::PmlMixed(arr)) => arr.iter().map(<$type>::try_from).collect(),
+                    _ => Err(Self::Error::InvalidType)
+                }
+            }
+        }
+
+        from_element!($type, Vec<$type>; &'a $type);
+    }
+}
+
+/// Implements conversions to `Option<T>` that return `None` for null values.
+macro_rules! optional {
+    ($($type:ty),*) => {
+        $(
+            impl<'a> TryFrom<&'a Element> for Option<$type> {
+                type Error = GetError;
+                fn try_from(elem: &'a Element) -> Result<Self, Self::Error> {
+                    match elem {
+                        Element::PmlNull => Ok(None),
+                        elem => <$type>::try_from(elem).map(Some)
+                    }
+                }
+            }
+        )*
+    }
+}
+
+macro_rules! optional_primitive {
+    ($($type:ty),*) => {
+        $(
+            optional!($type, &'a $type, Vec<$type>, &'a Vec<$type>);
+        )*
     }
 }
 
@@ -109,11 +222,11 @@ add_primitive!(PmlBool, bool);
 add_primitive!(PmlF32, f32);
 add_primitive!(PmlF64, f64, PmlF32);
 
-add_primitive!(PmlU8, u8);
+add_primitive!(PmlU8, u8 | PmlBytes);
 add_primitive!(PmlU16, u16, PmlU8);
 add_primitive!(PmlU32, u32, PmlU8, PmlU16);
-add_primitive!(PmlU64, u64, PmlU8, PmlU16, PmlU32);
-add_primitive!(PmlU128, u128, PmlU8, PmlU16, PmlU32, PmlU64);
+add_primitive!(PmlU64, u64, PmlU8, PmlU16, PmlU32; PmlByteSize);
+add_primitive!(PmlU128, u128, PmlU8, PmlU16, PmlU32, PmlU64; PmlByteSize);
 
 add_primitive!(PmlI8, i8);
 add_primitive!(PmlI16, i16, PmlI8);
@@ -124,8 +237,83 @@ add_primitive!(PmlI128, i128, PmlI8, PmlI16, PmlI32, PmlI64);
 generic_non_primitive!(PmlString, String);
 generic_non_primitive!(PmlStruct, PmlStruct);
 
+add_scalar!(PmlDate, Date);
+add_scalar!(PmlTime, Time);
+add_scalar!(PmlDateTime, DateTime);
+add_scalar!(PmlLocalDateTime, LocalDateTime);
+add_scalar!(PmlDuration, Duration);
+add_scalar!(PmlChar, char);
+add_scalar!(PmlEnum, EnumValue);
+#[cfg(feature = "bignum")]
+add_scalar!(PmlBigInt, BigInt);
+#[cfg(feature = "bignum")]
+add_scalar!(PmlDecimal, Decimal);
+
+impl TryFrom<&Element> for SystemTime {
+    type Error = GetError;
+    fn try_from(elem: &Element) -> Result<Self, Self::Error> {
+        match elem {
+            Element::PmlDateTime(d) => d.to_system_time().ok_or(Self::Error::InvalidType),
+            _ => Err(Self::Error::InvalidType)
+        }
+    }
+}
+
+from_element!(SystemTime, String, Vec<String>, Vec<PmlStruct>, Vec<Element>; &'a String, &'a Vec<Element>, &'a BTreeMap<String, Element>);
+
+impl<'a> FromElement<'a> for &'a PmlStruct {
+    fn from_element(elem: &'a Element) -> Result<Self, GetError> {
+        Self::try_from(elem)
+    }
+
+    fn from_struct(pml_struct: &'a PmlStruct) -> Result<Self, GetError> {
+        Ok(pml_struct)
+    }
+}
+
+/// Null values are converted to `None`, everything else like `T`.
+impl<'a, T: FromElement<'a>> FromElement<'a> for Option<T> {
+    fn from_element(elem: &'a Element) -> Result<Self, GetError> {
+        match elem {
+            Element::PmlNull => Ok(None),
+            elem => T::from_element(elem).map(Some)
+        }
+    }
+
+    fn from_copy(elem: Element) -> Result<Self, GetError> {
+        T::from_copy(elem).map(Some)
+    }
+
+    fn from_struct(pml_struct: &'a PmlStruct) -> Result<Self, GetError> {
+        T::from_struct(pml_struct).map(Some)
+    }
+}
+
+optional_primitive!(bool, f32, f64, u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, String);
+optional!(Date, &'a Date, Vec<Date>, Time, &'a Time, Vec<Time>, DateTime, &'a DateTime, Vec<DateTime>, LocalDateTime, &'a LocalDateTime, Vec<LocalDateTime>, SystemTime, Duration, &'a Duration, Vec<Duration>, char, &'a char, Vec<char>, EnumValue, &'a EnumValue, Vec<EnumValue>);
+#[cfg(feature = "bignum")]
+optional!(BigInt, &'a BigInt, Vec<BigInt>, Decimal, &'a Decimal, Vec<Decimal>);
+optional!(&'a PmlStruct, Vec<PmlStruct>, &'a Vec<PmlStruct>, Vec<Element>, &'a Vec<Element>);
+
+mod array_elem;
 mod error;
+mod map;
 mod pml_elem;
+pub(crate) mod serialize;
 mod string;
 mod structs;
 mod wip_elem;
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, PmlStruct};
+
+    #[test]
+    fn empty_arrays_convert_to_any_vec() {
+        let pml = parse::string("e = [];").expect("The input should be valid");
+        assert!(pml.get::<Vec<u8>>("e").expect("The array should convert").is_empty());
+        assert!(pml.get::<&Vec<f64>>("e").expect("The array should convert").is_empty());
+        assert!(pml.get::<Vec<String>>("e").expect("The array should convert").is_empty());
+        assert!(pml.get::<Vec<PmlStruct>>("e").expect("The array should convert").is_empty());
+    }
+}
@@ -1,10 +1,15 @@
 use core::fmt::Display;
-use crate::Element::{self, PmlStruct, PmlString, PmlBool, PmlI128, PmlI64, PmlI32, PmlI16, PmlI8, PmlU128, PmlU64, PmlU32, PmlU16, PmlU8, PmlF64, PmlF32, PmlArray};
-use std::fmt::{Formatter, Result};
+use crate::ArrayElement;
+use crate::Element::{self, PmlNull, PmlStruct, PmlString, PmlBool, PmlI128, PmlI64, PmlI32, PmlI16, PmlI8, PmlU128, PmlU64, PmlU32, PmlU16, PmlU8, PmlF64, PmlF32, PmlArray, PmlDate, PmlTime, PmlDateTime, PmlLocalDateTime, PmlDuration, PmlByteSize, PmlChar, PmlBytes, PmlEnum, PmlMap};
+#[cfg(feature = "bignum")]
+use crate::Element::{PmlBigInt, PmlDecimal};
+use crate::{units, encoding};
+use std::{fmt::{Formatter, Result}, hash::{Hash, Hasher}};
 
 impl Display for Element {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         match self {
+            PmlNull => write!(f, "null"),
             PmlString(s) => write!(f, "{s}"),
             PmlBool(b) => write!(f, "{b}"),
             PmlI128(i) => write!(f, "{i}"),
@@ -19,8 +24,85 @@ impl Display for Element {
             PmlU8(u) => write!(f, "{u}"),
             PmlF64(n) => write!(f, "{n}"),
             PmlF32(n) => write!(f, "{n}"),
+            PmlDate(d) => write!(f, "{d}"),
+            PmlTime(t) => write!(f, "{t}"),
+            PmlDateTime(d) => write!(f, "{d}"),
+            PmlLocalDateTime(d) => write!(f, "{d}"),
+            PmlDuration(d) => write!(f, "{}", units::format_duration(d)),
+            PmlByteSize(b) => write!(f, "{}", units::format_byte_size(*b)),
+            PmlChar(c) => write!(f, "{c}"),
+            PmlBytes(b) => write!(f, "{}", encoding::encode_base64(b)),
+            PmlEnum(e) => write!(f, "{}", e.variant()),
+            #[cfg(feature = "bignum")]
+            PmlBigInt(n) => write!(f, "{n}"),
+            #[cfg(feature = "bignum")]
+            PmlDecimal(n) => write!(f, "{n}"),
             PmlStruct(_) |
-            PmlArray(_) => write!(f, ""),
+            PmlArray(_) |
+            PmlMap(_) => write!(f, ""),
         }
     }
 }
+
+macro_rules! compare_and_hash {
+    ($($variant:ident),*; $($float:ident),*; $($(#[$attr:meta])* $scalar:ident),*) => {
+        impl PartialEq for Element {
+            fn eq(&self, other: &Self) -> bool {
+                match (self, other) {
+                    (PmlNull, PmlNull) => true,
+                    (PmlStruct(a), PmlStruct(b)) => a == b,
+                    (PmlArray(a), PmlArray(b)) => a == b,
+                    $(($variant(a), $variant(b)) => a == b,)*
+                    $(($float(a), $float(b)) => a.to_bits() == b.to_bits(),)*
+                    $($(#[$attr])* ($scalar(a), $scalar(b)) => a == b,)*
+                    _ => false
+                }
+            }
+        }
+
+        impl Eq for Element {}
+
+        impl Hash for Element {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.type_name().hash(state);
+                match self {
+                    PmlNull => (),
+                    PmlStruct(s) => s.hash(state),
+                    PmlArray(a) => a.hash(state),
+                    $($variant(v) => v.hash(state),)*
+                    $($float(f) => f.to_bits().hash(state),)*
+                    $($(#[$attr])* $scalar(v) => v.hash(state),)*
+                }
+            }
+        }
+
+        impl PartialEq for ArrayElement {
+            fn eq(&self, other: &Self) -> bool {
+                match (self, other) {
+                    (ArrayElement::PmlStruct(a), ArrayElement::PmlStruct(b)) => a == b,
+                    (ArrayElement::PmlMixed(a), ArrayElement::PmlMixed(b)) |
+                    (ArrayElement::PmlArray(a), ArrayElement::PmlArray(b)) => a == b,
+                    $((ArrayElement::$variant(a), ArrayElement::$variant(b)) => a == b,)*
+                    $((ArrayElement::$float(a), ArrayElement::$float(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.to_bits() == b.to_bits()),)*
+                    _ => false
+                }
+            }
+        }
+
+        impl Eq for ArrayElement {}
+
+        impl Hash for ArrayElement {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.len().hash(state);
+                match self {
+                    ArrayElement::PmlStruct(a) => a.hash(state),
+                    ArrayElement::PmlMixed(a) | ArrayElement::PmlArray(a) => a.hash(state),
+                    $(ArrayElement::$variant(a) => a.hash(state),)*
+                    $(ArrayElement::$float(a) => a.iter().for_each(|f| f.to_bits().hash(state)),)*
+                }
+            }
+        }
+    }
+}
+
+compare_and_hash!(PmlBool, PmlString, PmlI8, PmlI16, PmlI32, PmlI64, PmlI128, PmlU8, PmlU16, PmlU32, PmlU64, PmlU128; PmlF32, PmlF64; PmlDate, PmlTime, PmlDateTime, PmlLocalDateTime, PmlDuration, PmlByteSize, PmlChar, PmlBytes, PmlEnum, PmlMap, #[cfg(feature = "bignum")] PmlBigInt, #[cfg(feature = "bignum")] PmlDecimal);
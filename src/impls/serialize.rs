@@ -0,0 +1,356 @@
+use std::collections::{BTreeMap, BTreeSet};
+use crate::{Element, ArrayElement, PmlStruct, parse::is_char_reserved};
+
+const INDENT: &str = "    ";
+
+impl PmlStruct {
+    /// Serializes the struct to PML.
+    ///
+    /// Keys are sorted and numbers are written with their forced type, so parsing the result
+    /// returns an identical struct. Enums are declared with the variants that are used. There are
+    /// two exceptions: the payload of NaN floats is lost, and maps don't store the type of their
+    /// values, so an empty map is written as `<map<str>>` and a map of nulls as `<map<str?>>`.
+    pub fn to_pml_string(&self) -> String {
+        let mut res = String::new();
+        let mut enums = BTreeMap::new();
+        self.elements.values().for_each(|e| collect_enums(e, &mut enums));
+        for (name, variants) in &enums {
+            res.push_str("#enum ");
+            res.push_str(name);
+            res.push_str(": ");
+            res.push_str(&variants.iter().copied().collect::<Vec<_>>().join(", "));
+            res.push('\n');
+        }
+        if !enums.is_empty() {
+            res.push('\n');
+        }
+        write_elements(&mut res, self, Some(0));
+        res
+    }
+}
+
+impl Element {
+    /// Returns the name of the type of the element, as it would be used as a forced type. Arrays
+    /// are denoted like `[u8]`, nested arrays like `[[u8]]`.
+    pub fn type_name(&self) -> String {
+        match self {
+            Element::PmlNull => String::from("null"),
+            Element::PmlArray(arr) => format!("[{}]", array_type_name(arr)),
+            Element::PmlBool(_) => String::from("b"),
+            Element::PmlString(_) => String::from("str"),
+            Element::PmlStruct(_) => String::from("struct"),
+            Element::PmlF32(_) => String::from("f32"),
+            Element::PmlF64(_) => String::from("f64"),
+            Element::PmlI8(_) => String::from("i8"),
+            Element::PmlI16(_) => String::from("i16"),
+            Element::PmlI32(_) => String::from("i32"),
+            Element::PmlI64(_) => String::from("i64"),
+            Element::PmlI128(_) => String::from("i128"),
+            Element::PmlU8(_) => String::from("u8"),
+            Element::PmlU16(_) => String::from("u16"),
+            Element::PmlU32(_) => String::from("u32"),
+            Element::PmlU64(_) => String::from("u64"),
+            Element::PmlU128(_) => String::from("u128"),
+            Element::PmlDate(_) => String::from("date"),
+            Element::PmlTime(_) => String::from("time"),
+            Element::PmlDateTime(_) => String::from("datetime"),
+            Element::PmlLocalDateTime(_) => String::from("localdatetime"),
+            Element::PmlDuration(_) => String::from("duration"),
+            Element::PmlByteSize(_) => String::from("bytesize"),
+            Element::PmlChar(_) => String::from("char"),
+            Element::PmlBytes(_) => String::from("bytes"),
+            Element::PmlEnum(e) => e.name().to_string(),
+            Element::PmlMap(m) => format!("map<{}>", map_value_type(m)),
+            #[cfg(feature = "bignum")]
+            Element::PmlBigInt(_) => String::from("bigint"),
+            #[cfg(feature = "bignum")]
+            Element::PmlDecimal(_) => String::from("decimal"),
+        }
+    }
+}
+
+/// Returns the type of the values of the array, like `u8` or `[u8]` for nested arrays.
+pub(crate) fn array_type_name(arr: &ArrayElement) -> String {
+    match arr {
+        ArrayElement::PmlArray(a) => match a.first() {
+            Some(Element::PmlArray(inner)) => format!("[{}]", array_type_name(inner)),
+            _ => String::from("mixed")
+        },
+        arr => String::from(leaf_type_name(arr))
+    }
+}
+
+/// Returns the type of the values of the innermost arrays, which is written as forced type.
+fn leaf_type_name(arr: &ArrayElement) -> &'static str {
+    match arr {
+        ArrayElement::PmlBool(_) => "b",
+        ArrayElement::PmlString(_) => "str",
+        ArrayElement::PmlStruct(_) => "struct",
+        ArrayElement::PmlF32(_) => "f32",
+        ArrayElement::PmlF64(_) => "f64",
+        ArrayElement::PmlI8(_) => "i8",
+        ArrayElement::PmlI16(_) => "i16",
+        ArrayElement::PmlI32(_) => "i32",
+        ArrayElement::PmlI64(_) => "i64",
+        ArrayElement::PmlI128(_) => "i128",
+        ArrayElement::PmlU8(_) => "u8",
+        ArrayElement::PmlU16(_) => "u16",
+        ArrayElement::PmlU32(_) => "u32",
+        ArrayElement::PmlU64(_) => "u64",
+        ArrayElement::PmlU128(_) => "u128",
+        ArrayElement::PmlMixed(_) => "mixed",
+        ArrayElement::PmlArray(a) => match a.first() {
+            Some(Element::PmlArray(inner)) => leaf_type_name(inner),
+            _ => "mixed"
+        }
+    }
+}
+
+/// Returns the element as a PML value. Structs and arrays are written on a single line if no
+/// indentation level is provided.
+pub(crate) fn literal(elem: &Element, indent: Option<usize>) -> String {
+    let mut res = String::new();
+    write_literal(&mut res, elem, indent);
+    res
+}
+
+fn write_key(res: &mut String, key: &str) {
+    if !key.is_empty() && !key.chars().any(|c| c.is_whitespace() || is_char_reserved(c)) {
+        res.push_str(key);
+    }
+    else {
+        res.push('"');
+        res.push_str(key);
+        res.push('"');
+    }
+}
+
+/// Collects the names of the enums in the element together with the variants that are used.
+fn collect_enums<'a>(elem: &'a Element, enums: &mut BTreeMap<&'a str, BTreeSet<&'a str>>) {
+    match elem {
+        Element::PmlEnum(e) => {
+            enums.entry(e.name()).or_default().insert(e.variant());
+        }
+        Element::PmlStruct(s) => s.elements.values().for_each(|e| collect_enums(e, enums)),
+        Element::PmlMap(m) => m.values().for_each(|e| collect_enums(e, enums)),
+        Element::PmlArray(ArrayElement::PmlStruct(a)) => a.iter().flat_map(|s| s.elements.values()).for_each(|e| collect_enums(e, enums)),
+        Element::PmlArray(ArrayElement::PmlMixed(a) | ArrayElement::PmlArray(a)) => a.iter().for_each(|e| collect_enums(e, enums)),
+        _ => ()
+    }
+}
+
+fn write_string(res: &mut String, s: &str) {
+    res.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => res.push_str("\\\\"),
+            '"' => res.push_str("\\\""),
+            '\n' => res.push_str("\\n"),
+            '\t' => res.push_str("\\t"),
+            '\r' => res.push_str("\\r"),
+            '\0' => res.push_str("\\0"),
+            c if c.is_control() => res.push_str(&format!("\\u{{{:x}}}", u32::from(c))),
+            c => res.push(c)
+        }
+    }
+    res.push('"');
+}
+
+fn write_elements(res: &mut String, pml_struct: &PmlStruct, indent: Option<usize>) {
+    let mut elements: Vec<_> = pml_struct.elements.iter().collect();
+    elements.sort_by_key(|(key, _)| *key);
+    for (key, value) in elements {
+        match indent {
+            Some(n) => res.push_str(&INDENT.repeat(n)),
+            None => res.push(' ')
+        }
+        write_key(res, key);
+        res.push_str(" = ");
+        write_literal(res, value, indent);
+        res.push(';');
+        if indent.is_some() {
+            res.push('\n');
+        }
+    }
+}
+
+fn write_literal(res: &mut String, elem: &Element, indent: Option<usize>) {
+    match elem {
+        Element::PmlNull => res.push_str("null"),
+        Element::PmlBool(b) => res.push_str(&b.to_string()),
+        Element::PmlString(s) => write_string(res, s),
+        Element::PmlStruct(s) => {
+            res.push('{');
+            if s.elements.is_empty() {
+                res.push('}');
+                return;
+            }
+            if indent.is_some() {
+                res.push('\n');
+            }
+            write_elements(res, s, indent.map(|n| n + 1));
+            match indent {
+                Some(n) => res.push_str(&INDENT.repeat(n)),
+                None => res.push(' ')
+            }
+            res.push('}');
+        }
+        Element::PmlArray(arr) => write_array(res, arr, indent),
+        Element::PmlMap(m) => {
+            res.push('<');
+            res.push_str(&elem.type_name());
+            res.push_str("> {");
+            if m.is_empty() {
+                res.push('}');
+                return;
+            }
+            if indent.is_some() {
+                res.push('\n');
+            }
+            for (key, value) in m {
+                match indent {
+                    Some(n) => res.push_str(&INDENT.repeat(n + 1)),
+                    None => res.push(' ')
+                }
+                write_string(res, key);
+                res.push_str(" = ");
+                write_map_value(res, value, indent.map(|n| n + 1));
+                res.push(';');
+                if indent.is_some() {
+                    res.push('\n');
+                }
+            }
+            match indent {
+                Some(n) => res.push_str(&INDENT.repeat(n)),
+                None => res.push(' ')
+            }
+            res.push('}');
+        }
+        Element::PmlDate(_) | Element::PmlTime(_) | Element::PmlDateTime(_) | Element::PmlLocalDateTime(_) |
+        Element::PmlDuration(_) | Element::PmlByteSize(_) => res.push_str(&elem.to_string()),
+        Element::PmlChar(c) => {
+            res.push_str("<char> ");
+            write_string(res, &c.to_string());
+        }
+        Element::PmlEnum(e) => {
+            res.push('<');
+            res.push_str(e.name());
+            res.push_str("> ");
+            res.push_str(e.variant());
+        }
+        Element::PmlBytes(_) => {
+            res.push_str("<bytes> base64\"");
+            res.push_str(&elem.to_string());
+            res.push('"');
+        }
+        number => {
+            res.push('<');
+            res.push_str(&number.type_name());
+            res.push_str("> ");
+            match number {
+                Element::PmlF32(n) => res.push_str(&float_literal(n, n.is_sign_negative())),
+                Element::PmlF64(n) => res.push_str(&float_literal(n, n.is_sign_negative())),
+                number => res.push_str(&number.to_string())
+            }
+        }
+    }
+}
+
+/// Writes a float the way PML reads it back. Rust writes NaN as `NaN` without its sign, while PML
+/// uses `nan` and `-nan`. The payload of a NaN is not written, so it is lost.
+fn float_literal<T: ToString>(n: &T, sign_negative: bool) -> String {
+    match n.to_string() {
+        s if s == "NaN" && sign_negative => String::from("-nan"),
+        s if s == "NaN" => String::from("nan"),
+        s => s
+    }
+}
+
+/// Returns the forced type of the values of the map, like `u16`, or `u16?` if some of them are
+/// null. Maps only contain values of the same type. The declared type is not stored, so maps
+/// without a value other than null use `str`.
+fn map_value_type(map: &BTreeMap<String, Element>) -> String {
+    let name = match map.values().find(|v| !matches!(v, Element::PmlNull)) {
+        Some(Element::PmlArray(arr)) => String::from(leaf_type_name(arr)),
+        Some(value) => value.type_name(),
+        None => String::from("str")
+    };
+    match map.values().any(|v| matches!(v, Element::PmlNull)) {
+        true => name + "?",
+        false => name
+    }
+}
+
+/// Writes a value of a map without its forced type, since the map already has it.
+fn write_map_value(res: &mut String, elem: &Element, indent: Option<usize>) {
+    let mut value = String::new();
+    write_literal(&mut value, elem, indent);
+    match value.strip_prefix('<').and_then(|v| v.split_once("> ")) {
+        Some((_, untyped)) => res.push_str(untyped),
+        None => res.push_str(&value)
+    }
+}
+
+fn write_array(res: &mut String, arr: &ArrayElement, indent: Option<usize>) {
+    let leaf_type = leaf_type_name(arr);
+    if leaf_type != "mixed" {
+        res.push('<');
+        res.push_str(leaf_type);
+        res.push_str("> ");
+    }
+    write_array_values(res, arr, indent);
+}
+
+/// Writes the values of the array without a forced type. The values of nested arrays are written
+/// the same way, since they use the forced type of the outermost array.
+fn write_array_values(res: &mut String, arr: &ArrayElement, indent: Option<usize>) {
+    let values: Vec<String> = match arr {
+        ArrayElement::PmlBool(a) => a.iter().map(ToString::to_string).collect(),
+        ArrayElement::PmlString(a) => a.iter().map(|s| {
+            let mut res = String::new();
+            write_string(&mut res, s);
+            res
+        }).collect(),
+        ArrayElement::PmlStruct(a) => a.iter().map(|s| {
+            let mut res = String::new();
+            write_literal(&mut res, &Element::PmlStruct(Box::new(s.clone())), indent.map(|n| n + 1));
+            res
+        }).collect(),
+        ArrayElement::PmlF32(a) => a.iter().map(|n| float_literal(n, n.is_sign_negative())).collect(),
+        ArrayElement::PmlF64(a) => a.iter().map(|n| float_literal(n, n.is_sign_negative())).collect(),
+        ArrayElement::PmlI8(a) => a.iter().map(ToString::to_string).collect(),
+        ArrayElement::PmlI16(a) => a.iter().map(ToString::to_string).collect(),
+        ArrayElement::PmlI32(a) => a.iter().map(ToString::to_string).collect(),
+        ArrayElement::PmlI64(a) => a.iter().map(ToString::to_string).collect(),
+        ArrayElement::PmlI128(a) => a.iter().map(ToString::to_string).collect(),
+        ArrayElement::PmlU8(a) => a.iter().map(ToString::to_string).collect(),
+        ArrayElement::PmlU16(a) => a.iter().map(ToString::to_string).collect(),
+        ArrayElement::PmlU32(a) => a.iter().map(ToString::to_string).collect(),
+        ArrayElement::PmlU64(a) => a.iter().map(ToString::to_string).collect(),
+        ArrayElement::PmlU128(a) => a.iter().map(ToString::to_string).collect(),
+        ArrayElement::PmlMixed(a) => a.iter().map(|e| literal(e, indent.map(|n| n + 1))).collect(),
+        ArrayElement::PmlArray(a) => a.iter().map(|e| {
+            let mut res = String::new();
+            match e {
+                Element::PmlArray(inner) => write_array_values(&mut res, inner, indent.map(|n| n + 1)),
+                e => write_literal(&mut res, e, indent.map(|n| n + 1))
+            }
+            res
+        }).collect(),
+    };
+    res.push('[');
+    match indent {
+        Some(n) if matches!(arr, ArrayElement::PmlStruct(_) | ArrayElement::PmlMixed(_) | ArrayElement::PmlArray(_)) && !values.is_empty() => {
+            for value in values {
+                res.push('\n');
+                res.push_str(&INDENT.repeat(n + 1));
+                res.push_str(&value);
+                res.push(',');
+            }
+            res.push('\n');
+            res.push_str(&INDENT.repeat(n));
+        }
+        _ => res.push_str(&values.join(", "))
+    }
+    res.push(']');
+}
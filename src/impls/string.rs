@@ -26,7 +26,19 @@ impl TryFrom<&Element> for String {
             Element::PmlU128(i) => Ok(i.to_string()),
             Element::PmlF32(f) => Ok(f.to_string()),
             Element::PmlF64(f) => Ok(f.to_string()),
+            Element::PmlDate(d) => Ok(d.to_string()),
+            Element::PmlTime(t) => Ok(t.to_string()),
+            Element::PmlDateTime(d) => Ok(d.to_string()),
+            Element::PmlLocalDateTime(d) => Ok(d.to_string()),
+            Element::PmlDuration(_) | Element::PmlByteSize(_) => Ok(elem.to_string()),
+            Element::PmlChar(c) => Ok(c.to_string()),
+            Element::PmlEnum(e) => Ok(e.variant().to_string()),
+            Element::PmlBytes(_) => Ok(elem.to_string()),
+            #[cfg(feature = "bignum")]
+            Element::PmlBigInt(_) | Element::PmlDecimal(_) => Ok(elem.to_string()),
+            Element::PmlNull |
             Element::PmlStruct(_) |
+            Element::PmlMap(_) |
             Element::PmlArray(_) => Err(Self::Error::InvalidType)
         }
     }
@@ -50,6 +62,7 @@ impl TryFrom<&Element> for Vec<String> {
             A(E::PmlI128(arr)) => Ok(arr.iter().map(TS::to_string).collect()),
             A(E::PmlF32(arr)) => Ok(arr.iter().map(TS::to_string).collect()),
             A(E::PmlF64(arr)) => Ok(arr.iter().map(TS::to_string).collect()),
+            A(E::PmlMixed(arr)) => arr.iter().map(String::try_from).collect(),
             _ => Err(Self::Error::InvalidType)
         }
     }
@@ -1,5 +1,5 @@
 use crate::{Element, PmlStruct, parse::WIPStruct, elem::ArrayElement, GetError};
-use std::collections::HashMap;
+use std::{collections::HashMap, hash::{Hash, Hasher}};
 
 impl From<HashMap<String, Element>> for Element {
     fn from(elements: HashMap<String, Element>) -> Self {
@@ -19,8 +19,174 @@ impl TryFrom<&Element> for Vec<PmlStruct> {
     type Error = GetError;
     fn try_from(value: &Element) -> Result<Self, Self::Error> {
         match value {
+            Element::PmlArray(ArrayElement::PmlMixed(e)) if e.is_empty() => Ok(Vec::new()),
             Element::PmlArray(ArrayElement::PmlStruct(e)) => Ok(e.clone()),
             _ => Err(GetError::InvalidType)
         }
     }
 }
+
+impl From<PmlStruct> for Element {
+    fn from(value: PmlStruct) -> Self {
+        Element::PmlStruct(Box::new(value))
+    }
+}
+
+impl PartialEq for PmlStruct {
+    fn eq(&self, other: &Self) -> bool {
+        self.elements == other.elements
+    }
+}
+
+impl Eq for PmlStruct {}
+
+impl Hash for PmlStruct {
+    /// Hashes the elements ordered by key, so equal structs always have the same hash.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut elements: Vec<_> = self.elements.iter().collect();
+        elements.sort_by_key(|(key, _)| *key);
+        elements.len().hash(state);
+        for (key, value) in elements {
+            key.hash(state);
+            value.hash(state);
+        }
+    }
+}
+
+/// The 64 bit FNV-1a hash function.
+struct Fnv(u64);
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Writes a length as 8 bytes, so the result does not depend on the size of `usize`.
+    fn write_len(&mut self, len: usize) {
+        self.write(&(len as u64).to_le_bytes());
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write_len(s.len());
+        self.write(s.as_bytes());
+    }
+
+    /// Writes the elements ordered by key, so equal structs always result in the same bytes.
+    fn write_struct(&mut self, pml_struct: &PmlStruct) {
+        let mut elements: Vec<_> = pml_struct.elements.iter().collect();
+        elements.sort_by_key(|(key, _)| *key);
+        self.write_len(elements.len());
+        for (key, value) in elements {
+            self.write_str(key);
+            self.write_element(value);
+        }
+    }
+
+    /// Writes the type name of the element followed by its value. Numbers are written in
+    /// little-endian byte order, floats as their bit pattern, and values of other types as
+    /// their text.
+    fn write_element(&mut self, elem: &Element) {
+        self.write_str(&elem.type_name());
+        match elem {
+            Element::PmlNull => (),
+            Element::PmlBool(b) => self.write(&[u8::from(*b)]),
+            Element::PmlString(s) => self.write_str(s),
+            Element::PmlStruct(s) => self.write_struct(s),
+            Element::PmlArray(arr) => {
+                self.write_len(arr.len());
+                for value in (0..arr.len()).filter_map(|i| arr.get_element(i)) {
+                    self.write_element(&value);
+                }
+            }
+            Element::PmlMap(map) => {
+                self.write_len(map.len());
+                for (key, value) in map {
+                    self.write_str(key);
+                    self.write_element(value);
+                }
+            }
+            Element::PmlF32(n) => self.write(&n.to_bits().to_le_bytes()),
+            Element::PmlF64(n) => self.write(&n.to_bits().to_le_bytes()),
+            Element::PmlI8(n) => self.write(&n.to_le_bytes()),
+            Element::PmlI16(n) => self.write(&n.to_le_bytes()),
+            Element::PmlI32(n) => self.write(&n.to_le_bytes()),
+            Element::PmlI64(n) => self.write(&n.to_le_bytes()),
+            Element::PmlI128(n) => self.write(&n.to_le_bytes()),
+            Element::PmlU8(n) => self.write(&n.to_le_bytes()),
+            Element::PmlU16(n) => self.write(&n.to_le_bytes()),
+            Element::PmlU32(n) => self.write(&n.to_le_bytes()),
+            Element::PmlU64(n) | Element::PmlByteSize(n) => self.write(&n.to_le_bytes()),
+            Element::PmlU128(n) => self.write(&n.to_le_bytes()),
+            Element::PmlChar(c) => self.write(&u32::from(*c).to_le_bytes()),
+            Element::PmlBytes(bytes) => {
+                self.write_len(bytes.len());
+                self.write(bytes);
+            }
+            _ => self.write_str(&elem.to_string())
+        }
+    }
+}
+
+impl PmlStruct {
+    /// Returns a fingerprint of the content of the struct.
+    ///
+    /// Structs that are [equal](PartialEq) always have the same fingerprint, independent of the
+    /// order in which their elements were added. The fingerprint is computed from a fixed byte
+    /// encoding of the content, so it is the same across runs, platforms and Rust versions.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+        hasher.write_struct(self);
+        hasher.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, PmlStruct};
+    use std::{collections::hash_map::DefaultHasher, hash::{Hash, Hasher}};
+
+    fn pml(input: &str) -> PmlStruct {
+        parse::string(input).expect("The input should be valid")
+    }
+
+    fn hash(value: &PmlStruct) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn equal_structs_ignore_key_order() {
+        let a = pml("a = 1; b = \"x\"; c = {d = [1, 2];};");
+        let b = pml("c = {d = [1, 2];}; b = \"x\"; a = 1;");
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert_eq!(a.fingerprint(), b.fingerprint());
+    }
+
+    #[test]
+    fn different_values_are_not_equal() {
+        let a = pml("a = 1; b = [1, 2];");
+        let b = pml("a = 1; b = [2, 1];");
+        assert_ne!(a, b);
+        assert_ne!(a.fingerprint(), b.fingerprint());
+    }
+
+    #[test]
+    fn different_types_are_not_equal() {
+        let a = pml("a = <u8> 1;");
+        let b = pml("a = <u16> 1;");
+        assert_ne!(a, b);
+        assert_ne!(a.fingerprint(), b.fingerprint());
+    }
+
+    #[test]
+    fn fingerprint_is_stable() {
+        let a = pml("a = 1;");
+        assert_eq!(a.fingerprint(), pml("a = 1;").fingerprint());
+        assert_ne!(a.fingerprint(), pml("").fingerprint());
+    }
+}
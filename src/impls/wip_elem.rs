@@ -1,5 +1,5 @@
 use std::{rc::Rc, cell::RefCell};
-use crate::{parse::{WIPElement, ISElem, WIPStruct}, elem::Element};
+use crate::{parse::{WIPElement, WIPArrayItem, ISElem, WIPStruct}, elem::{Element, ArrayElement}};
 
 impl<T> From<T> for WIPElement
 where Element: From<T> {
@@ -31,3 +31,22 @@ impl From<Vec<(usize, Rc<RefCell<WIPStruct>>)>> for WIPElement {
         Self::StructArray(value)
     }
 }
+
+/// Turns a value with a forced type into a value of an array whose type is inferred. Typed arrays
+/// keep their type if they are empty, otherwise it is inferred from their values again.
+impl From<WIPElement> for WIPArrayItem {
+    fn from(value: WIPElement) -> Self {
+        match value {
+            WIPElement::Element(elem) | WIPElement::InferredNumber(elem) => Self::Element(elem),
+            WIPElement::IncompleteString(inc_str) => Self::String(inc_str),
+            WIPElement::Struct(s) => Self::Struct(s),
+            WIPElement::StringArray(arr) if arr.is_empty() => Self::Element(Element::PmlArray(ArrayElement::PmlString(Vec::new()))),
+            WIPElement::StringArray(arr) => Self::Array(arr.into_iter().map(|(_, s)| Self::String(s)).collect()),
+            WIPElement::StructArray(arr) if arr.is_empty() => Self::Element(Element::PmlArray(ArrayElement::PmlStruct(Vec::new()))),
+            WIPElement::StructArray(arr) => Self::Array(arr.into_iter().map(|(_, s)| Self::Struct(s)).collect()),
+            WIPElement::Array(arr) => Self::Array(arr),
+            WIPElement::TypedArray(arr, forced_type) => Self::TypedArray(arr, forced_type),
+            WIPElement::Expression(expr) => Self::Expression(expr)
+        }
+    }
+}
@@ -3,18 +3,53 @@
 //! PML is a language for storing complex data in a human-readable format.
 //! For more information, see the [PML specification](https://gist.github.com/PhilVoel/00a6acb11257d00a84451f9df4d5b340).
 
-use std::collections::HashMap;
+use std::collections::{HashMap, hash_map};
 
 mod impls;
 pub mod parse;
 pub mod errors;
-pub use errors::{ParseError, ParseNumberError, GetError};
+pub mod entry;
+pub mod merge;
+pub mod loader;
+pub mod diff;
+pub mod patch;
+pub mod datetime;
+pub mod enums;
+pub mod functions;
+#[cfg(feature = "bignum")]
+pub mod bignum;
+mod units;
+mod encoding;
+mod format_spec;
+mod path;
+pub use errors::{ParseError, ParseNumberError, GetError, MergeError, LoadError, PatchError};
 
 mod elem {
-    use crate::PmlStruct;
+    use std::{collections::BTreeMap, time::Duration};
+    use crate::{PmlStruct, datetime::{Date, Time, DateTime, LocalDateTime}, enums::EnumValue};
+    #[cfg(feature = "bignum")]
+    use crate::bignum::{BigInt, Decimal};
 
+    /// A single value stored in a [`PmlStruct`].
+    ///
+    /// Elements are equal if they have the same type and value. Floats are compared by their bit
+    /// pattern, so `NaN` is equal to itself if it has the same payload, while `0.0` and `-0.0` are
+    /// different. This makes the comparison reflexive, so elements implement [`Eq`] and [`Hash`].
+    ///
+    /// `PmlNull` is an explicitly unset value, written as `null`. Durations like `1h30m` and byte
+    /// sizes like `64KiB` are stored as `PmlDuration` and as number of bytes in `PmlByteSize`.
+    /// `PmlBytes` holds binary data, written like `<bytes> hex"00ff"` or `<bytes> base64"AP8="`.
+    /// `PmlEnum` is a variant of an [enum](crate::enums) declared in the file. `PmlMap` maps keys
+    /// that may contain any characters to values of the same type, written like
+    /// `<map<u16>> { "example.com" = 443; }` and referenced like `|ports["example.com"]|`.
+    /// With the `bignum` feature, `PmlBigInt` and `PmlDecimal` store numbers of
+    /// [arbitrary size](crate::bignum).
+    ///
+    /// New types may be added in minor versions, so matches need a wildcard arm.
     #[derive(Debug, Clone)]
+    #[non_exhaustive]
     pub enum Element {
+        PmlNull,
         PmlArray(ArrayElement),
         PmlBool(bool),
         PmlString(String),
@@ -31,9 +66,30 @@ mod elem {
         PmlU32(u32),
         PmlU64(u64),
         PmlU128(u128),
+        PmlDate(Date),
+        PmlTime(Time),
+        PmlDateTime(DateTime),
+        PmlLocalDateTime(LocalDateTime),
+        PmlDuration(Duration),
+        PmlByteSize(u64),
+        PmlChar(char),
+        PmlBytes(Vec<u8>),
+        PmlEnum(EnumValue),
+        PmlMap(BTreeMap<String, Element>),
+        #[cfg(feature = "bignum")]
+        PmlBigInt(BigInt),
+        #[cfg(feature = "bignum")]
+        PmlDecimal(Decimal),
     }
 
+    /// An array of values that all have the same type, except for `PmlMixed`, which may contain
+    /// values of any type. `PmlArray` contains arrays, which are all [`Element::PmlArray`]s of the
+    /// same type, like the rows of `<u8> [[1, 2], [3, 4]]`.
+    ///
+    /// Arrays are compared like [`Element`]s. Like `Element`, the enum may get new variants in
+    /// minor versions.
     #[derive(Debug, Clone)]
+    #[non_exhaustive]
     pub enum ArrayElement {
         PmlBool(Vec<bool>),
         PmlStruct(Vec<PmlStruct>),
@@ -50,41 +106,178 @@ mod elem {
         PmlU32(Vec<u32>),
         PmlU64(Vec<u64>),
         PmlU128(Vec<u128>),
+        PmlMixed(Vec<Element>),
+        PmlArray(Vec<Element>),
     }
 }
-use elem::Element;
+pub use elem::{Element, ArrayElement};
+
+/// A type of the values of an [`ArrayElement`], used to borrow them with
+/// [`get_array`](PmlStruct::get_array).
+///
+/// Values of `PmlMixed` and `PmlArray` arrays are borrowed as [`Element`]s.
+pub trait ArrayType: Sized {
+    /// Returns the values of the array if it contains values of this type.
+    fn from_array(arr: &ArrayElement) -> Option<&[Self]>;
+}
+
+/// A type that values can be returned as by [`get`](PmlStruct::get).
+///
+/// Most values are converted from the [`Element`] they are stored as. Values of arrays with a
+/// single type, like `ports[0]` of `<u16> [80, 443]`, are not stored as elements, so they are
+/// converted from a copy instead. Structs in struct arrays are converted from the struct.
+pub trait FromElement<'a>: Sized {
+    /// Converts a stored element.
+    ///
+    /// # Errors
+    /// This function returns an error if the element has the wrong type.
+    fn from_element(elem: &'a Element) -> Result<Self, GetError>;
+
+    /// Converts a copy of a value of an array with a single type. Types that borrow from the
+    /// element can not be returned for such values.
+    ///
+    /// # Errors
+    /// This function returns an error if the value has the wrong type.
+    fn from_copy(elem: Element) -> Result<Self, GetError> {
+        let _ = elem;
+        Err(GetError::InvalidType)
+    }
 
-#[derive(Clone, Debug)]
+    /// Converts a struct of a struct array.
+    ///
+    /// # Errors
+    /// This function returns an error if `Self` is not a struct.
+    fn from_struct(pml_struct: &'a PmlStruct) -> Result<Self, GetError> {
+        let _ = pml_struct;
+        Err(GetError::InvalidType)
+    }
+}
+
+use entry::{Entry, OccupiedEntry, VacantEntry};
+use path::Segment;
+
+/// Stands in for missing elements, so they can be converted to `None`.
+static NULL: Element = Element::PmlNull;
+
+/// A value that was found at a path in a struct.
+enum Found<'a> {
+    Element(&'a Element),
+    /// A copy of a value of an array with a single type.
+    Copy(Element),
+    /// A struct of a struct array.
+    Struct(&'a PmlStruct),
+}
+
+/// A container that holds key-value pairs of data.
+///
+/// Structs are equal if they contain the same keys with [equal](Element) values.
+#[derive(Clone, Debug, Default)]
 pub struct PmlStruct {
     elements: HashMap<String, Element>,
 }
 
 /// A container that holds key-value pairs of data.
 impl PmlStruct {
+    /// Creates an empty struct.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
     /// Returns the value of the element with the provided key.
     ///
     /// Takes a key to the element that should be returned. Returns the element as type `T` if the
     /// conversion could be performed, or an error if one occured.
     ///
+    /// Keys can index into arrays, like `servers[0].port`, `servers[0]` or `matrix[1]`. Keys of
+    /// maps are written in quotes, like `hosts["example.com"]`, so they are not split at dots. Values
+    /// of arrays with a single type, like `<u8> [1, 2]`, are copied, so they can only be returned
+    /// as owned types, see [`FromElement`].
+    ///
+    /// Null values and missing elements are both returned as `None` if `T` is an [`Option`], while
+    /// values of the wrong type are still an error.
+    ///
     /// # Errors
     /// This function returns an error if the element does not exist, or if the element exists, but
     /// could not be converted to the requested type.
-    pub fn get<'a, T>(&'a self, key: &str) -> Result<T, GetError>
-        where
-        T: TryFrom<&'a Element, Error = GetError>
-        {
-            match key.split_once('.') {
-                None => match self.elements.get(key).map(|elem| T::try_from(elem)) {
-                    None => Err(GetError::DoesNotExits),
-                    Some(res) => res
+    pub fn get<'a, T: FromElement<'a>>(&'a self, key: &str) -> Result<T, GetError> {
+        match self.find(key) {
+            Err(GetError::DoesNotExits) => T::from_element(&NULL).map_err(|_| GetError::DoesNotExits),
+            Err(e) => Err(e),
+            Ok(Found::Element(elem)) => T::from_element(elem),
+            Ok(Found::Copy(elem)) => T::from_copy(elem),
+            Ok(Found::Struct(pml_struct)) => T::from_struct(pml_struct)
+        }
+    }
+
+    /// Borrows the values of the array with the provided key.
+    ///
+    /// Works for every type of array, see [`ArrayType`]. Keys can index into arrays like with
+    /// [`get`](Self::get). Empty arrays without a forced type can be borrowed as any type.
+    ///
+    /// # Errors
+    /// This function returns an error if the element does not exist, or if it is not an array
+    /// with values of type `T`.
+    pub fn get_array<T: ArrayType>(&self, key: &str) -> Result<&[T], GetError> {
+        match self.get_element(key)? {
+            Element::PmlArray(ArrayElement::PmlMixed(a)) if a.is_empty() => Ok(&[]),
+            Element::PmlArray(arr) => T::from_array(arr).ok_or(GetError::InvalidType),
+            _ => Err(GetError::InvalidType)
+        }
+    }
+
+    /// Borrows the structs of the struct array with the provided key.
+    ///
+    /// This is a shorthand for [`get_array::<PmlStruct>`](Self::get_array).
+    ///
+    /// # Errors
+    /// This function returns an error if the element does not exist, or if it is not a struct
+    /// array.
+    pub fn get_structs(&self, key: &str) -> Result<&[PmlStruct], GetError> {
+        self.get_array(key)
+    }
+
+    /// Returns the element with the provided key. Values of arrays with a single type and structs
+    /// in struct arrays are not stored as elements, so they are an error.
+    pub(crate) fn get_element(&self, key: &str) -> Result<&Element, GetError> {
+        match self.find(key)? {
+            Found::Element(elem) => Ok(elem),
+            Found::Copy(_) | Found::Struct(_) => Err(GetError::InvalidType)
+        }
+    }
+
+    fn find(&self, key: &str) -> Result<Found<'_>, GetError> {
+        match path::parse(key) {
+            Some(segments) => self.element_at(&segments),
+            None => Err(GetError::DoesNotExits)
+        }
+    }
+
+    fn element_at(&self, segments: &[Segment]) -> Result<Found<'_>, GetError> {
+        let Some(Segment::Key(key)) = segments.first() else {
+            return Err(GetError::InvalidType);
+        };
+        let mut elem = self.elements.get(*key).ok_or(GetError::DoesNotExits)?;
+        for (i, segment) in segments.iter().enumerate().skip(1) {
+            let is_last = i + 1 == segments.len();
+            elem = match (elem, segment) {
+                (Element::PmlStruct(s), Segment::Key(key)) => s.elements.get(*key).ok_or(GetError::DoesNotExits)?,
+                (Element::PmlMap(m), Segment::Key(key)) => m.get(*key).ok_or(GetError::DoesNotExits)?,
+                (Element::PmlArray(ArrayElement::PmlMixed(a) | ArrayElement::PmlArray(a)), Segment::Index(n)) => a.get(*n).ok_or(GetError::DoesNotExits)?,
+                (Element::PmlArray(ArrayElement::PmlStruct(a)), Segment::Index(n)) => {
+                    let pml_struct = a.get(*n).ok_or(GetError::DoesNotExits)?;
+                    return match is_last {
+                        true => Ok(Found::Struct(pml_struct)),
+                        false => pml_struct.element_at(&segments[i + 1..])
+                    };
                 }
-                Some((first, rest)) => match self.elements.get(first) {
-                    Some(Element::PmlStruct(s)) => s.get::<T>(rest),
-                    Some(_) => Err(GetError::InvalidType),
-                    None => Err(GetError::DoesNotExits)
+                (Element::PmlArray(arr), Segment::Index(n)) if is_last => {
+                    return arr.get_element(*n).map(Found::Copy).ok_or(GetError::DoesNotExits);
                 }
-            }
+                _ => return Err(GetError::InvalidType)
+            };
         }
+        Ok(Found::Element(elem))
+    }
 
     /// Adds an element to the struct.
     ///
@@ -120,4 +313,34 @@ impl PmlStruct {
                 }
             }
         }
+
+    /// Gets the entry for the element with the provided key for in-place manipulation.
+    ///
+    /// Works like [`HashMap::entry`]: the returned [`Entry`] can be used to modify an existing
+    /// element or to insert one if it does not exist yet. Should the key point into unexisting
+    /// structs, they will be created once a value is inserted, just like with [`add`](Self::add).
+    ///
+    /// # Errors
+    /// This function returns an error if the key is invalid, or if the key points into an
+    /// existing element that is not a struct.
+    pub fn entry(&mut self, key: &str) -> Result<Entry<'_>, ParseError> {
+        if key.split('.').any(str::is_empty) {
+            return Err(ParseError::InvalidKey);
+        }
+        let (first, rest) = match key.split_once('.') {
+            Some((first, rest)) => (first, Some(rest)),
+            None => (key, None)
+        };
+        if !self.elements.contains_key(first) {
+            return Ok(Entry::Vacant(VacantEntry{parent: self, key: String::from(key)}));
+        }
+        match (self.elements.entry(String::from(first)), rest) {
+            (hash_map::Entry::Occupied(entry), None) => Ok(Entry::Occupied(OccupiedEntry{entry})),
+            (hash_map::Entry::Occupied(entry), Some(rest)) => match entry.into_mut() {
+                Element::PmlStruct(s) => s.entry(rest),
+                _ => Err(ParseError::NotAStruct(String::from(first)))
+            }
+            (hash_map::Entry::Vacant(_), _) => unreachable!("Existence was checked above")
+        }
+    }
 }
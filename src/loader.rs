@@ -0,0 +1,301 @@
+//! A loader that combines multiple configuration sources into a single [`PmlStruct`].
+//!
+//! See [`ConfigLoader`] for more information.
+
+use std::{collections::{HashMap, HashSet}, env, io::ErrorKind, path::PathBuf};
+use crate::{Element, PmlStruct, parse, path, entry::Entry, enums::EnumValue, functions::Functions, merge::MergePolicy, errors::{LoadError as Error, ParseError}};
+
+type LoadedLayer = (PmlStruct, HashMap<String, Layer>);
+
+/// Builder that stacks configuration sources in priority order and merges them into a single
+/// [`PmlStruct`].
+///
+/// Sources are applied in the order in which they are added, so values of later sources override
+/// values of earlier ones.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLoader {
+    sources: Vec<Source>,
+    policy: MergePolicy,
+    functions: Functions,
+}
+
+#[derive(Debug, Clone)]
+enum Source {
+    Defaults(String),
+    File(PathBuf),
+    OptionalFile(PathBuf),
+    Env(String),
+    Args(Vec<String>),
+}
+
+/// The layer a value of a [`LoadedConfig`] came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Layer {
+    /// The value was defined in [embedded defaults](ConfigLoader::defaults).
+    Defaults,
+    /// The value was defined in the file with the provided path.
+    File(PathBuf),
+    /// The value was defined in the environment variable with the provided name.
+    Env(String),
+    /// The value was defined in the provided command-line override.
+    Arg(String),
+}
+
+/// The result of [loading](ConfigLoader::load) a configuration.
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    /// The merged configuration.
+    pub config: PmlStruct,
+    /// The layer every value of the configuration came from, keyed by its full path. This is the
+    /// last layer that changed the value while merging, so a value that a later layer could not
+    /// override keeps its original layer. Arrays count as a single value, so an array that
+    /// combines the values of several layers names the last of them.
+    pub provenance: HashMap<String, Layer>,
+}
+
+impl ConfigLoader {
+    /// Creates a loader without any sources.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the policy that is used to merge the sources. By default, later values replace earlier
+    /// ones, including arrays.
+    #[must_use]
+    pub fn merge_policy(mut self, policy: MergePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the [functions](crate::functions) that can be called in the values of all sources. By
+    /// default, these are the built-in functions of [`Functions::new`], which can not read
+    /// environment variables or files.
+    #[must_use]
+    pub fn functions(mut self, functions: Functions) -> Self {
+        self.functions = functions;
+        self
+    }
+
+    /// Adds PML content that is embedded in the program, e.g. with [`include_str`].
+    #[must_use]
+    pub fn defaults(mut self, content: &str) -> Self {
+        self.sources.push(Source::Defaults(String::from(content)));
+        self
+    }
+
+    /// Adds a PML file that must exist.
+    #[must_use]
+    pub fn file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.sources.push(Source::File(path.into()));
+        self
+    }
+
+    /// Adds a PML file that is skipped if it does not exist.
+    #[must_use]
+    pub fn optional_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.sources.push(Source::OptionalFile(path.into()));
+        self
+    }
+
+    /// Adds all environment variables that start with the prefix followed by an underscore.
+    ///
+    /// The rest of the name is converted to lowercase and double underscores separate nested
+    /// keys, so `APP_DB__HOST` with the prefix `APP` becomes `db.host`. Values are strings, unless
+    /// they replace a value of another type from an earlier source. Then they are parsed like a
+    /// value of that type in a PML file, so `APP_PORT=8080` replaces `port = <u16> 80;` with a
+    /// `u16`. Function calls are not allowed in them.
+    #[must_use]
+    pub fn env(mut self, prefix: &str) -> Self {
+        self.sources.push(Source::Env(String::from(prefix)));
+        self
+    }
+
+    /// Adds command-line overrides in the form `key=value`, where the key may point into nested
+    /// structs like `db.host=example.org`. Values are parsed like environment variables.
+    #[must_use]
+    pub fn args<I, S>(mut self, args: I) -> Self
+        where
+        I: IntoIterator<Item = S>,
+        S: Into<String>
+        {
+            self.sources.push(Source::Args(args.into_iter().map(Into::into).collect()));
+            self
+        }
+
+    /// Loads all sources and merges them.
+    ///
+    /// # Errors
+    /// This function returns an error if a source could not be read or parsed, if an override is
+    /// not in the form `key=value`, or if the sources could not be merged.
+    pub fn load(&self) -> Result<LoadedConfig, Error> {
+        let mut config = PmlStruct::new();
+        let mut provenance = HashMap::new();
+        for source in &self.sources {
+            let Some((layer_struct, layers)) = load_source(source, &self.functions, &config)? else {
+                continue;
+            };
+            let layers: Vec<(String, Layer)> = layers.into_iter().collect();
+            let before: Vec<Option<Element>> = layers.iter().map(|(key, _)| config.get_element(key).ok().cloned()).collect();
+            config.merge(layer_struct, &self.policy)?;
+            for ((key, layer), before) in layers.into_iter().zip(before) {
+                if config.get_element(&key).ok() != before.as_ref() {
+                    provenance.insert(key, layer);
+                }
+            }
+        }
+        let mut leaves = Vec::new();
+        collect_leaves(&config, "", &mut leaves);
+        let leaves: HashSet<String> = leaves.into_iter().collect();
+        provenance.retain(|k, _| leaves.contains(k));
+        Ok(LoadedConfig{config, provenance})
+    }
+}
+
+impl LoadedConfig {
+    /// Returns the layer the value with the provided key came from.
+    pub fn source(&self, key: &str) -> Option<&Layer> {
+        self.provenance.get(key)
+    }
+}
+
+fn load_source(source: &Source, functions: &Functions, config: &PmlStruct) -> Result<Option<LoadedLayer>, Error> {
+    let (layer_struct, layer) = match source {
+        Source::Defaults(content) => (parse::string_with_functions(content, functions), Layer::Defaults),
+        Source::File(path) => (parse::file_with_functions(&path.to_string_lossy(), functions), Layer::File(path.clone())),
+        Source::OptionalFile(path) => match parse::file_with_functions(&path.to_string_lossy(), functions) {
+            Err(ParseError::FileAccess(e)) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            res => (res, Layer::File(path.clone()))
+        }
+        Source::Env(prefix) => {
+            let prefix = format!("{prefix}_");
+            let mut vars: Vec<_> = env::vars().filter_map(|(k, v)| Some((k.strip_prefix(&prefix)?.to_string(), v, k))).collect();
+            vars.sort();
+            let overrides = vars.into_iter().map(|(k, v, name)| (k.split("__").map(str::to_lowercase).collect::<Vec<_>>().join("."), v, Layer::Env(name)));
+            return load_overrides(overrides, config).map(Some);
+        }
+        Source::Args(args) => {
+            let mut overrides = Vec::new();
+            for arg in args {
+                let (k, v) = arg.split_once('=').ok_or_else(|| Error::InvalidOverride(arg.clone()))?;
+                overrides.push((String::from(k.trim()), String::from(v.trim()), Layer::Arg(arg.clone())));
+            }
+            return load_overrides(overrides, config).map(Some);
+        }
+    };
+    let layer_struct = layer_struct.map_err(|error| Error::Parse{layer: layer.clone(), error})?;
+    let mut leaves = Vec::new();
+    collect_leaves(&layer_struct, "", &mut leaves);
+    Ok(Some((layer_struct, leaves.into_iter().map(|k| (k, layer.clone())).collect())))
+}
+
+fn load_overrides<I>(overrides: I, config: &PmlStruct) -> Result<LoadedLayer, Error>
+    where
+    I: IntoIterator<Item = (String, String, Layer)>
+    {
+        let mut layer_struct = PmlStruct::new();
+        let mut provenance = HashMap::new();
+        for (key, value, layer) in overrides {
+            let existing = config.get_element(&key).ok();
+            let value = coerce(&value, existing).ok_or_else(|| Error::OverrideTypeMismatch {
+                layer: layer.clone(),
+                expected: existing.map(Element::type_name).unwrap_or_default()
+            })?;
+            let mut leaves = Vec::new();
+            collect_leaves_of(&value, &key, &mut leaves);
+            match layer_struct.entry(&key).map_err(|error| Error::Parse{layer: layer.clone(), error})? {
+                Entry::Occupied(mut entry) => {
+                    entry.insert(value);
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+            }
+            provenance.extend(leaves.into_iter().map(|k| (k, layer.clone())));
+        }
+        Ok((layer_struct, provenance))
+    }
+
+/// Converts an override to the type of the value it replaces. Overrides of strings and of values
+/// that do not exist yet stay strings, so `01234` keeps its leading zero. Returns `None` if the
+/// override is not a valid value of the type.
+fn coerce(value: &str, existing: Option<&Element>) -> Option<Element> {
+    let existing = match existing {
+        None | Some(Element::PmlString(_) | Element::PmlNull) => return Some(String::from(value).into()),
+        Some(Element::PmlEnum(e)) if !value.is_empty() && value.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+            return Some(Element::PmlEnum(EnumValue::new(e.name(), value)));
+        }
+        Some(Element::PmlArray(arr)) if arr.len() == 0 => {
+            return parse::value(value).ok().filter(|parsed| matches!(parsed, Element::PmlArray(_)));
+        }
+        Some(existing) => existing
+    };
+    let type_name = existing.type_name();
+    let forced_type = type_name.trim_start_matches('[').trim_end_matches(']');
+    let parsed = parse::value(&format!("<{forced_type}> {value}")).or_else(|_| parse::value(value)).ok()?;
+    (parsed.type_name() == type_name).then_some(parsed)
+}
+
+fn collect_leaves(pml_struct: &PmlStruct, path: &str, leaves: &mut Vec<String>) {
+    for (key, value) in &pml_struct.elements {
+        collect_leaves_of(value, &path::join(path, key), leaves);
+    }
+}
+
+fn collect_leaves_of(value: &Element, path: &str, leaves: &mut Vec<String>) {
+    match value {
+        Element::PmlStruct(s) if !s.elements.is_empty() => collect_leaves(s, path, leaves),
+        _ => leaves.push(String::from(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use crate::{Element, errors::LoadError};
+    use super::{ConfigLoader, Layer};
+
+    const DEFAULTS: &str = "name = \"app\"; zip = \"0\"; port = <u16> 80; ratio = <f64> 1.0; hosts = <str> [\"a\"]; db = { host = \"localhost\"; };";
+
+    #[test]
+    fn coerces_overrides_to_replaced_types() {
+        let loaded = ConfigLoader::new().defaults(DEFAULTS).args(["port=8080", "ratio=1.10", "hosts=[\"b\", \"c\"]"]).load().unwrap();
+        assert!(matches!(loaded.config.get_element("port"), Ok(Element::PmlU16(8080))));
+        assert!(matches!(loaded.config.get_element("ratio"), Ok(Element::PmlF64(n)) if *n == 1.1));
+        assert_eq!(loaded.config.get_array::<String>("hosts").unwrap(), ["b", "c"]);
+        assert_eq!(loaded.source("port"), Some(&Layer::Arg(String::from("port=8080"))));
+        assert_eq!(loaded.source("name"), Some(&Layer::Defaults));
+    }
+
+    #[test]
+    fn keeps_strings_as_written() {
+        let loaded = ConfigLoader::new().defaults(DEFAULTS).args(["zip=01234", "version=1.10", "db.user=007"]).load().unwrap();
+        assert_eq!(loaded.config.get::<String>("zip").unwrap(), "01234");
+        assert_eq!(loaded.config.get::<String>("version").unwrap(), "1.10");
+        assert_eq!(loaded.config.get::<String>("db.user").unwrap(), "007");
+    }
+
+    #[test]
+    fn does_not_call_functions_in_overrides() {
+        let loaded = ConfigLoader::new().defaults(DEFAULTS).args(["name=file(\"/etc/hostname\")"]).load().unwrap();
+        assert_eq!(loaded.config.get::<String>("name").unwrap(), "file(\"/etc/hostname\")");
+        let res = ConfigLoader::new().defaults(DEFAULTS).args(["port=file(\"/etc/hostname\")"]).load();
+        assert!(matches!(res, Err(LoadError::OverrideTypeMismatch{expected, ..}) if expected == "u16"));
+    }
+
+    #[test]
+    fn reads_prefixed_environment_variables() {
+        env::set_var("PML_LOADER_TEST_DB__HOST", "example.org");
+        env::set_var("PML_LOADER_TEST_PORT", "443");
+        let loaded = ConfigLoader::new().defaults(DEFAULTS).env("PML_LOADER_TEST").load().unwrap();
+        assert_eq!(loaded.config.get::<String>("db.host").unwrap(), "example.org");
+        assert!(matches!(loaded.config.get_element("port"), Ok(Element::PmlU16(443))));
+        assert_eq!(loaded.source("db.host"), Some(&Layer::Env(String::from("PML_LOADER_TEST_DB__HOST"))));
+    }
+
+    #[test]
+    fn reports_invalid_sources() {
+        assert!(matches!(ConfigLoader::new().args(["port"]).load(), Err(LoadError::InvalidOverride(arg)) if arg == "port"));
+        assert!(matches!(ConfigLoader::new().file("/nonexistent/config.pml").load(), Err(LoadError::Parse{..})));
+        assert!(ConfigLoader::new().optional_file("/nonexistent/config.pml").load().is_ok());
+    }
+}
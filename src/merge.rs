@@ -0,0 +1,254 @@
+//! Types for [merging](crate::PmlStruct::merge) one [`PmlStruct`] into another.
+
+use crate::{Element, ArrayElement, PmlStruct, path, errors::MergeError as Error, impls::serialize::array_type_name};
+
+/// Decides how conflicts are handled when [merging](crate::PmlStruct::merge) two structs.
+#[derive(Debug, Clone, Default)]
+pub struct MergePolicy {
+    /// How conflicting values that are not structs or arrays are handled.
+    pub scalars: ScalarPolicy,
+    /// How two arrays with the same key are combined.
+    pub arrays: ArrayPolicy,
+}
+
+/// Decides which value is kept when both structs contain different values with the same key.
+///
+/// This also applies when the types of the values differ, e.g. when one is a struct and the other
+/// one is not, and to arrays that are combined with [`ArrayPolicy::Replace`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScalarPolicy {
+    /// Keep the value of the struct that is merged into.
+    PreferLeft,
+    /// Use the value of the struct that is merged in.
+    #[default]
+    PreferRight,
+    /// Abort the merge with a [`MergeError::Conflict`](crate::errors::MergeError::Conflict).
+    Error,
+}
+
+/// Decides how two arrays with the same key are combined.
+///
+/// Except for `Replace`, an empty array fits arrays of any type, so it is combined with another
+/// array by keeping the other one. With `MergeByKey`, the other one must still be a struct array
+/// whose structs contain the merge key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ArrayPolicy {
+    /// Keep one of the arrays, according to the [`ScalarPolicy`].
+    #[default]
+    Replace,
+    /// Append the values of the array that is merged in. Both arrays must have the same type.
+    Append,
+    /// Merge values with the same index, using the [`ScalarPolicy`] for values that are not structs.
+    /// Additional values are appended. Both arrays must have the same type.
+    MergeByIndex,
+    /// Merge structs in struct arrays that have the same value for the provided key. Structs without
+    /// a match are appended. Both arrays must be struct arrays.
+    MergeByKey(String),
+}
+
+/// Information about what happened during a [merge](crate::PmlStruct::merge).
+#[derive(Debug, Clone, Default)]
+pub struct MergeReport {
+    /// The full paths of all values that were overridden, ordered by key. Elements of arrays are denoted by their index, e.g. `servers[0].port`.
+    pub overridden: Vec<String>,
+}
+
+macro_rules! append_arrays {
+    ($left:expr, $right:expr, $($variant:ident),*) => {
+        match ($left, $right) {
+            $(
+                (ArrayElement::$variant(l), ArrayElement::$variant(r)) => {
+                    l.extend(r);
+                    true
+                }
+            )*
+            _ => false
+        }
+    }
+}
+
+macro_rules! merge_arrays_by_index {
+    ($left:expr, $right:expr, $path:expr, $policy:expr, $report:expr, $($variant:ident),*) => {
+        match ($left, $right) {
+            $(
+                (ArrayElement::$variant(l), ArrayElement::$variant(r)) => {
+                    for (i, value) in r.into_iter().enumerate() {
+                        if i >= l.len() {
+                            l.push(value);
+                        }
+                        else if l[i] != value && keep_right($policy, format!("{}[{i}]", $path), $report)? {
+                            l[i] = value;
+                        }
+                    }
+                    true
+                }
+            )*
+            _ => false
+        }
+    }
+}
+
+impl PmlStruct {
+    /// Merges another struct into this one.
+    ///
+    /// Nested structs are merged recursively. Values that only exist in `other` are added, conflicts
+    /// between values are resolved according to the provided policy. Returns a report with the keys
+    /// of all values that were overridden.
+    ///
+    /// # Errors
+    /// This function returns an error if the policy does not allow a conflict that was found, or if
+    /// two arrays could not be combined. In that case, this struct is left unchanged.
+    pub fn merge(&mut self, other: PmlStruct, policy: &MergePolicy) -> Result<MergeReport, Error> {
+        let backup = self.clone();
+        let mut report = MergeReport::default();
+        match merge_structs(self, other, "", policy, &mut report) {
+            Ok(()) => Ok(report),
+            Err(e) => {
+                *self = backup;
+                Err(e)
+            }
+        }
+    }
+}
+
+fn keep_right(policy: &MergePolicy, path: String, report: &mut MergeReport) -> Result<bool, Error> {
+    match policy.scalars {
+        ScalarPolicy::PreferLeft => Ok(false),
+        ScalarPolicy::PreferRight => {
+            report.overridden.push(path);
+            Ok(true)
+        }
+        ScalarPolicy::Error => Err(Error::Conflict{key: path})
+    }
+}
+
+fn merge_structs(left: &mut PmlStruct, right: PmlStruct, path: &str, policy: &MergePolicy, report: &mut MergeReport) -> Result<(), Error> {
+    let mut right_elements: Vec<_> = right.elements.into_iter().collect();
+    right_elements.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (key, right_value) in right_elements {
+        let path = path::join(path, &key);
+        let Some(left_value) = left.elements.get_mut(&key) else {
+            left.elements.insert(key, right_value);
+            continue;
+        };
+        match (left_value, right_value) {
+            (Element::PmlStruct(l), Element::PmlStruct(r)) => merge_structs(l, *r, &path, policy, report)?,
+            (Element::PmlArray(l), Element::PmlArray(r)) => merge_arrays(l, r, &path, policy, report)?,
+            (l, r) => if *l != r && keep_right(policy, path, report)? {
+                *l = r;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn merge_arrays(left: &mut ArrayElement, right: ArrayElement, path: &str, policy: &MergePolicy, report: &mut MergeReport) -> Result<(), Error> {
+    match &policy.arrays {
+        ArrayPolicy::Replace => if *left != right && keep_right(policy, String::from(path), report)? {
+            *left = right;
+        }
+        _ if right.len() == 0 => (),
+        ArrayPolicy::Append | ArrayPolicy::MergeByIndex if left.len() == 0 => *left = right,
+        ArrayPolicy::Append | ArrayPolicy::MergeByIndex if array_type_name(left) != array_type_name(&right) => {
+            return Err(Error::ArrayTypeMismatch{key: String::from(path)});
+        }
+        ArrayPolicy::Append => if !append_arrays!(left, right, PmlBool, PmlStruct, PmlString, PmlF32, PmlF64, PmlI8, PmlI16, PmlI32, PmlI64, PmlI128, PmlU8, PmlU16, PmlU32, PmlU64, PmlU128, PmlMixed, PmlArray) {
+            return Err(Error::ArrayTypeMismatch{key: String::from(path)});
+        }
+        ArrayPolicy::MergeByIndex => match (left, right) {
+            (ArrayElement::PmlStruct(l), ArrayElement::PmlStruct(r)) => {
+                for (i, value) in r.into_iter().enumerate() {
+                    match l.get_mut(i) {
+                        Some(s) => merge_structs(s, value, &format!("{path}[{i}]"), policy, report)?,
+                        None => l.push(value)
+                    }
+                }
+            }
+            (l, r) => if !merge_arrays_by_index!(l, r, path, policy, report, PmlBool, PmlString, PmlF32, PmlF64, PmlI8, PmlI16, PmlI32, PmlI64, PmlI128, PmlU8, PmlU16, PmlU32, PmlU64, PmlU128, PmlMixed, PmlArray) {
+                return Err(Error::ArrayTypeMismatch{key: String::from(path)});
+            }
+        }
+        ArrayPolicy::MergeByKey(merge_key) => match (left, right) {
+            (l, ArrayElement::PmlStruct(r)) if l.len() == 0 => {
+                let mut structs = Vec::new();
+                merge_by_key(&mut structs, r, merge_key, path, policy, report)?;
+                *l = ArrayElement::PmlStruct(structs);
+            }
+            (ArrayElement::PmlStruct(l), ArrayElement::PmlStruct(r)) => merge_by_key(l, r, merge_key, path, policy, report)?,
+            _ => return Err(Error::ArrayTypeMismatch{key: String::from(path)})
+        }
+    }
+    Ok(())
+}
+
+/// Merges the structs with the same value for the merge key and appends the others. Every struct
+/// that is merged in must contain the merge key, even if there is nothing to merge it with.
+fn merge_by_key(left: &mut Vec<PmlStruct>, right: Vec<PmlStruct>, merge_key: &str, path: &str, policy: &MergePolicy, report: &mut MergeReport) -> Result<(), Error> {
+    for (i, value) in right.into_iter().enumerate() {
+        let id = value.get_element(merge_key).map_err(|_| Error::MissingMergeKey{key: format!("{path}[{i}]")})?;
+        match left.iter().position(|s| s.get_element(merge_key).ok() == Some(id)) {
+            Some(i) => merge_structs(&mut left[i], value, &format!("{path}[{i}]"), policy, report)?,
+            None => left.push(value)
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, PmlStruct, errors::MergeError};
+    use super::{MergePolicy, ScalarPolicy, ArrayPolicy};
+
+    fn pml(input: &str) -> PmlStruct {
+        parse::string(input).expect("The input should be valid")
+    }
+
+    fn policy(scalars: ScalarPolicy, arrays: ArrayPolicy) -> MergePolicy {
+        MergePolicy{scalars, arrays}
+    }
+
+    #[test]
+    fn merges_nested_structs() {
+        let mut left = pml("server = { host = \"a\"; port = <u16> 80; };");
+        let report = left.merge(pml("server = { port = <u16> 8080; tls = true; };"), &MergePolicy::default()).unwrap();
+        assert_eq!(report.overridden, vec![String::from("server.port")]);
+        assert_eq!(left.get::<String>("server.host").unwrap(), "a");
+        assert_eq!(left.get::<u16>("server.port").unwrap(), 8080);
+        assert!(left.get::<bool>("server.tls").unwrap());
+    }
+
+    #[test]
+    fn keeps_left_values_on_conflict_errors() {
+        let mut left = pml("a = <u8> 1; b = <u8> 1;");
+        let res = left.merge(pml("a = <u8> 2; b = <u8> 2;"), &policy(ScalarPolicy::Error, ArrayPolicy::Replace));
+        assert!(matches!(res, Err(MergeError::Conflict{key}) if key == "a"));
+        assert_eq!(left, pml("a = <u8> 1; b = <u8> 1;"));
+    }
+
+    #[test]
+    fn combines_arrays() {
+        let mut left = pml("a = <u8> [1, 2]; e = [];");
+        left.merge(pml("a = <u8> [3]; e = <str> [\"x\"];"), &policy(ScalarPolicy::PreferRight, ArrayPolicy::Append)).unwrap();
+        assert_eq!(left.get_array::<u8>("a").unwrap(), [1, 2, 3]);
+        assert_eq!(left.get_array::<String>("e").unwrap(), ["x"]);
+        let res = left.merge(pml("a = <u16> [4];"), &policy(ScalarPolicy::PreferRight, ArrayPolicy::Append));
+        assert!(matches!(res, Err(MergeError::ArrayTypeMismatch{key}) if key == "a"));
+    }
+
+    #[test]
+    fn merges_struct_arrays_by_key() {
+        let by_name = policy(ScalarPolicy::PreferRight, ArrayPolicy::MergeByKey(String::from("name")));
+        let mut left = pml("s = [{name = \"a\"; port = <u16> 1;}];");
+        left.merge(pml("s = [{name = \"a\"; port = <u16> 2;}, {name = \"b\"; port = <u16> 3;}];"), &by_name).unwrap();
+        assert_eq!(left, pml("s = [{name = \"a\"; port = <u16> 2;}, {name = \"b\"; port = <u16> 3;}];"));
+    }
+
+    #[test]
+    fn requires_merge_key_even_if_left_is_empty() {
+        let by_name = policy(ScalarPolicy::PreferRight, ArrayPolicy::MergeByKey(String::from("name")));
+        for left in ["s = [];", "s = [{name = \"a\";}];"] {
+            let res = pml(left).merge(pml("s = [{name = \"b\";}, {port = <u16> 3;}];"), &by_name);
+            assert!(matches!(res, Err(MergeError::MissingMergeKey{key}) if key == "s[1]"), "{left}");
+        }
+    }
+}
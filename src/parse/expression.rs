@@ -0,0 +1,548 @@
+use std::{rc::Rc, cell::RefCell, cmp::Ordering, fmt::{Debug, Formatter, Result as FmtResult}};
+use crate::{Element, ArrayElement, errors::ParseError as Error, functions::{Function, Signature}};
+use super::WIPStruct;
+
+/// An expression like `|workers| * 4`, which is evaluated once all values it references are
+/// resolved. Integers are calculated in the common type of their operands, like `u8` for
+/// `<u8> 200 + <u8> 50`. Numbers without forced type take the type of the other operand, and if
+/// neither has one, the result gets the smallest type it fits into.
+#[derive(Debug)]
+pub(crate) struct Expression {
+    /// The full path of the key the expression is assigned to, for errors.
+    pub key: String,
+    pub expr: Expr,
+}
+
+#[derive(Debug)]
+pub(crate) enum Expr {
+    Value(Element),
+    /// A number literal without forced type. Integers take the type of the other operand.
+    Number(Element),
+    Variable(Rc<RefCell<WIPStruct>>, String),
+    Not(Box<Expr>),
+    Negate(Box<Expr>),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+    Call(Call),
+    Array(Vec<Expr>),
+}
+
+/// A call of a function like `upper(|name|)`.
+pub(crate) struct Call {
+    pub name: String,
+    pub signature: Signature,
+    pub function: Function,
+    pub args: Vec<Expr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+}
+
+enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+/// The type of an integer, as whether it is signed and its number of bits.
+#[derive(Clone, Copy)]
+struct IntType {
+    signed: bool,
+    bits: u32,
+}
+
+impl Debug for Call {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("Call").field("name", &self.name).field("args", &self.args).finish()
+    }
+}
+
+impl Operator {
+    /// All operators, with the ones that start with another operator first.
+    pub const ALL: [Operator; 13] = [
+        Operator::Equal, Operator::NotEqual, Operator::LessEqual, Operator::GreaterEqual, Operator::And, Operator::Or,
+        Operator::Add, Operator::Subtract, Operator::Multiply, Operator::Divide, Operator::Remainder, Operator::Less, Operator::Greater
+    ];
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Operator::Add => "+",
+            Operator::Subtract => "-",
+            Operator::Multiply => "*",
+            Operator::Divide => "/",
+            Operator::Remainder => "%",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::Less => "<",
+            Operator::LessEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterEqual => ">=",
+            Operator::And => "&&",
+            Operator::Or => "||",
+        }
+    }
+
+    /// How strongly the operator binds its operands. Higher values bind stronger.
+    pub fn precedence(self) -> u8 {
+        match self {
+            Operator::Or => 1,
+            Operator::And => 2,
+            Operator::Equal | Operator::NotEqual | Operator::Less | Operator::LessEqual | Operator::Greater | Operator::GreaterEqual => 3,
+            Operator::Add | Operator::Subtract => 4,
+            Operator::Multiply | Operator::Divide | Operator::Remainder => 5,
+        }
+    }
+}
+
+impl Expression {
+    /// Evaluates the expression. Returns `None` if a value it references is not resolved yet. The
+    /// lookup returns the value of a variable and whether it is a number whose type was inferred.
+    pub fn evaluate(&self, lookup: &impl Fn(&Rc<RefCell<WIPStruct>>, &str) -> Option<(Element, bool)>) -> Result<Option<Element>, Error> {
+        evaluate(&self.expr, lookup, &self.key)
+    }
+
+    /// Checks if the result is a number whose type is inferred, because the expression only
+    /// consists of numbers whose type is inferred.
+    pub fn is_inferred(&self, lookup: &impl Fn(&Rc<RefCell<WIPStruct>>, &str) -> Option<(Element, bool)>) -> bool {
+        is_untyped(&self.expr, lookup)
+    }
+}
+
+fn evaluate(expr: &Expr, lookup: &impl Fn(&Rc<RefCell<WIPStruct>>, &str) -> Option<(Element, bool)>, key: &str) -> Result<Option<Element>, Error> {
+    let value = match expr {
+        Expr::Value(value) | Expr::Number(value) => value.clone(),
+        Expr::Variable(link, name) => match lookup(link, name) {
+            Some((value, _)) => value,
+            None => return Ok(None)
+        }
+        Expr::Not(inner) => {
+            let Some(value) = evaluate(inner, lookup, key)? else {
+                return Ok(None);
+            };
+            match value {
+                Element::PmlBool(b) => Element::PmlBool(!b),
+                value => return Err(invalid_operands(key, format!("!{}", value.type_name())))
+            }
+        }
+        Expr::Negate(inner) => {
+            let Some(value) = evaluate(inner, lookup, key)? else {
+                return Ok(None);
+            };
+            match (&value, number(&value, key)?) {
+                (Element::PmlF32(n), _) => Element::PmlF32(-n),
+                (Element::PmlF64(n), _) => Element::PmlF64(-n),
+                (_, Some(Number::Int(n))) => {
+                    let result = n.checked_neg().ok_or_else(|| overflow(key))?;
+                    match is_untyped(inner, lookup) {
+                        true => integer(result),
+                        false => typed_integer(result, int_type(&value)).ok_or_else(|| overflow(key))?
+                    }
+                }
+                _ => return Err(invalid_operands(key, format!("-{}", value.type_name())))
+            }
+        }
+        Expr::Binary(op @ (Operator::And | Operator::Or), left, right) => {
+            let Some(left) = evaluate(left, lookup, key)? else {
+                return Ok(None);
+            };
+            match (op, left) {
+                (Operator::And, Element::PmlBool(false)) => Element::PmlBool(false),
+                (Operator::Or, Element::PmlBool(true)) => Element::PmlBool(true),
+                (_, Element::PmlBool(_)) => {
+                    let Some(right) = evaluate(right, lookup, key)? else {
+                        return Ok(None);
+                    };
+                    match right {
+                        Element::PmlBool(b) => Element::PmlBool(b),
+                        right => return Err(invalid_operands(key, format!("bool {} {}", op.symbol(), right.type_name())))
+                    }
+                }
+                (_, left) => return Err(invalid_operands(key, format!("{} {} ...", left.type_name(), op.symbol())))
+            }
+        }
+        Expr::Binary(op, left_expr, right_expr) => {
+            let (Some(left), Some(right)) = (evaluate(left_expr, lookup, key)?, evaluate(right_expr, lookup, key)?) else {
+                return Ok(None);
+            };
+            let types = (int_type(&left).filter(|_| !is_untyped(left_expr, lookup)), int_type(&right).filter(|_| !is_untyped(right_expr, lookup)));
+            binary(*op, &left, &right, types, key)?
+        }
+        Expr::Conditional(condition, then, otherwise) => {
+            let Some(condition) = evaluate(condition, lookup, key)? else {
+                return Ok(None);
+            };
+            match condition {
+                Element::PmlBool(true) => return evaluate(then, lookup, key),
+                Element::PmlBool(false) => return evaluate(otherwise, lookup, key),
+                condition => return Err(invalid_operands(key, format!("{} ? ... : ...", condition.type_name())))
+            }
+        }
+        Expr::Call(call) => {
+            let mut args = Vec::with_capacity(call.args.len());
+            for arg in &call.args {
+                match evaluate(arg, lookup, key)? {
+                    Some(arg) => args.push(arg),
+                    None => return Ok(None)
+                }
+            }
+            if !call.signature.accepts(&args) {
+                let types: Vec<String> = args.iter().map(Element::type_name).collect();
+                return Err(invalid_operands(key, format!("{}({})", call.name, types.join(", "))));
+            }
+            (call.function)(&args).map_err(|message| Error::FunctionFailed {
+                key: key.to_string(),
+                function: call.name.clone(),
+                message
+            })?
+        }
+        Expr::Array(items) => {
+            let mut values = Vec::with_capacity(items.len());
+            for item in items {
+                match evaluate(item, lookup, key)? {
+                    Some(value) => values.push(value),
+                    None => return Ok(None)
+                }
+            }
+            Element::PmlArray(ArrayElement::infer(values))
+        }
+    };
+    Ok(Some(value))
+}
+
+/// Applies the operator to the values. Integer arithmetic happens in the common type of the
+/// integer types of the operands, which is `None` for numbers without forced type. If both are
+/// `None`, the result has the smallest type it fits into.
+fn binary(op: Operator, left: &Element, right: &Element, types: (Option<IntType>, Option<IntType>), key: &str) -> Result<Element, Error> {
+    let mismatch = || invalid_operands(key, format!("{} {} {}", left.type_name(), op.symbol(), right.type_name()));
+    let ordering = match (number(left, key)?, number(right, key)?) {
+        (Some(Number::Int(l)), Some(Number::Int(r))) => {
+            if matches!(op, Operator::Divide | Operator::Remainder) && r == 0 {
+                return Err(Error::DivisionByZero {
+                    key: key.to_string()
+                });
+            }
+            let result = match op {
+                Operator::Add => l.checked_add(r),
+                Operator::Subtract => l.checked_sub(r),
+                Operator::Multiply => l.checked_mul(r),
+                Operator::Divide => l.checked_div(r),
+                Operator::Remainder => l.checked_rem(r),
+                _ => return compare(op, Some(l.cmp(&r))).ok_or_else(mismatch)
+            };
+            let result = result.ok_or_else(|| overflow(key))?;
+            let int_type = match types {
+                (Some(l), Some(r)) => Some(l.common(r)),
+                (l, r) => l.or(r)
+            };
+            return typed_integer(result, int_type).ok_or_else(|| overflow(key));
+        }
+        (Some(l), Some(r)) => {
+            let (l, r) = (l.as_f64(), r.as_f64());
+            let result = match op {
+                Operator::Add => l + r,
+                Operator::Subtract => l - r,
+                Operator::Multiply => l * r,
+                Operator::Divide => l / r,
+                Operator::Remainder => l % r,
+                _ => return compare(op, l.partial_cmp(&r)).ok_or_else(mismatch)
+            };
+            #[allow(clippy::cast_possible_truncation)]
+            return Ok(match (left, right) {
+                (Element::PmlF64(_), _) | (_, Element::PmlF64(_)) => Element::PmlF64(result),
+                _ => Element::PmlF32(result as f32)
+            });
+        }
+        _ => match (left, right) {
+            (Element::PmlString(l), Element::PmlString(r)) if op == Operator::Add => return Ok(Element::PmlString(format!("{l}{r}"))),
+            (Element::PmlArray(l), Element::PmlArray(r)) if op == Operator::Add => return concat(l, r).ok_or_else(mismatch),
+            (Element::PmlString(l), Element::PmlString(r)) => Some(l.cmp(r)),
+            _ if matches!(op, Operator::Equal | Operator::NotEqual) => Some(if left == right { Ordering::Equal } else { Ordering::Less }),
+            _ => None
+        }
+    };
+    compare(op, ordering).ok_or_else(mismatch)
+}
+
+/// Concatenates two arrays. Their type is inferred like the one of an array literal, so numbers
+/// are unified, but other values have to have the same type unless both arrays are mixed. Empty
+/// arrays can be concatenated with arrays of any type.
+fn concat(left: &ArrayElement, right: &ArrayElement) -> Option<Element> {
+    let is_mixed = |arr: &ArrayElement| arr.len() == 0 || matches!(arr, ArrayElement::PmlMixed(_));
+    let values = [left, right].into_iter().flat_map(|arr| (0..arr.len()).filter_map(|i| arr.get_element(i))).collect();
+    let result = ArrayElement::infer(values);
+    if result.len() > 0 && matches!(result, ArrayElement::PmlMixed(_)) && !(is_mixed(left) && is_mixed(right)) {
+        return None;
+    }
+    Some(Element::PmlArray(result))
+}
+
+/// Checks if the left value is less than the right one. Returns `None` if they can not be
+/// compared.
+pub(crate) fn is_less(left: &Element, right: &Element) -> Option<bool> {
+    match binary(Operator::Less, left, right, (None, None), "") {
+        Ok(Element::PmlBool(less)) => Some(less),
+        _ => None
+    }
+}
+
+/// Applies a comparison operator to the ordering of its operands. Returns `None` if the operator
+/// is not a comparison. Values without ordering, like `nan`, are never equal.
+fn compare(op: Operator, ordering: Option<Ordering>) -> Option<Element> {
+    let result = match op {
+        Operator::Equal => ordering == Some(Ordering::Equal),
+        Operator::NotEqual => ordering != Some(Ordering::Equal),
+        Operator::Less => ordering == Some(Ordering::Less),
+        Operator::LessEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        Operator::Greater => ordering == Some(Ordering::Greater),
+        Operator::GreaterEqual => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        _ => return None
+    };
+    Some(Element::PmlBool(result))
+}
+
+/// Returns the value of a number element. Integers that do not fit into `i128` overflow.
+fn number(elem: &Element, key: &str) -> Result<Option<Number>, Error> {
+    Ok(Some(match elem {
+        Element::PmlI8(n) => Number::Int((*n).into()),
+        Element::PmlI16(n) => Number::Int((*n).into()),
+        Element::PmlI32(n) => Number::Int((*n).into()),
+        Element::PmlI64(n) => Number::Int((*n).into()),
+        Element::PmlI128(n) => Number::Int(*n),
+        Element::PmlU8(n) => Number::Int((*n).into()),
+        Element::PmlU16(n) => Number::Int((*n).into()),
+        Element::PmlU32(n) => Number::Int((*n).into()),
+        Element::PmlU64(n) => Number::Int((*n).into()),
+        Element::PmlU128(n) => Number::Int(i128::try_from(*n).map_err(|_| overflow(key))?),
+        Element::PmlF32(n) => Number::Float((*n).into()),
+        Element::PmlF64(n) => Number::Float(*n),
+        _ => return Ok(None)
+    }))
+}
+
+impl Number {
+    #[allow(clippy::cast_precision_loss)]
+    fn as_f64(&self) -> f64 {
+        match self {
+            Number::Int(n) => *n as f64,
+            Number::Float(n) => *n
+        }
+    }
+}
+
+impl IntType {
+    /// Returns the smallest type that holds all values of both types, up to 128 bits.
+    fn common(self, other: IntType) -> IntType {
+        let (signed, unsigned) = match (self.signed, other.signed) {
+            (true, false) => (self, other),
+            (false, true) => (other, self),
+            _ => return IntType { signed: self.signed, bits: self.bits.max(other.bits) }
+        };
+        match signed.bits > unsigned.bits {
+            true => signed,
+            false => IntType { signed: true, bits: (unsigned.bits * 2).min(128) }
+        }
+    }
+}
+
+/// Checks if the expression only consists of numbers without forced type, so its result takes the
+/// type of the other operand.
+fn is_untyped(expr: &Expr, lookup: &impl Fn(&Rc<RefCell<WIPStruct>>, &str) -> Option<(Element, bool)>) -> bool {
+    match expr {
+        Expr::Number(_) => true,
+        Expr::Variable(link, name) => lookup(link, name).is_some_and(|(_, inferred)| inferred),
+        Expr::Negate(inner) => is_untyped(inner, lookup),
+        Expr::Binary(Operator::Add | Operator::Subtract | Operator::Multiply | Operator::Divide | Operator::Remainder, left, right) => {
+            is_untyped(left, lookup) && is_untyped(right, lookup)
+        }
+        Expr::Conditional(_, then, otherwise) => is_untyped(then, lookup) && is_untyped(otherwise, lookup),
+        _ => false
+    }
+}
+
+/// Returns the type of an integer element.
+fn int_type(elem: &Element) -> Option<IntType> {
+    let (signed, bits) = match elem {
+        Element::PmlI8(_) => (true, 8),
+        Element::PmlI16(_) => (true, 16),
+        Element::PmlI32(_) => (true, 32),
+        Element::PmlI64(_) => (true, 64),
+        Element::PmlI128(_) => (true, 128),
+        Element::PmlU8(_) => (false, 8),
+        Element::PmlU16(_) => (false, 16),
+        Element::PmlU32(_) => (false, 32),
+        Element::PmlU64(_) => (false, 64),
+        Element::PmlU128(_) => (false, 128),
+        _ => return None
+    };
+    Some(IntType { signed, bits })
+}
+
+/// Returns the integer as element of the type, or with the smallest type it fits into if there is
+/// no type. Returns `None` if it does not fit into the type.
+fn typed_integer(n: i128, int_type: Option<IntType>) -> Option<Element> {
+    let Some(IntType { signed, bits }) = int_type else {
+        return Some(integer(n));
+    };
+    match (signed, bits) {
+        (true, 8) => i8::try_from(n).ok().map(Element::from),
+        (true, 16) => i16::try_from(n).ok().map(Element::from),
+        (true, 32) => i32::try_from(n).ok().map(Element::from),
+        (true, 64) => i64::try_from(n).ok().map(Element::from),
+        (true, _) => Some(Element::PmlI128(n)),
+        (false, 8) => u8::try_from(n).ok().map(Element::from),
+        (false, 16) => u16::try_from(n).ok().map(Element::from),
+        (false, 32) => u32::try_from(n).ok().map(Element::from),
+        (false, 64) => u64::try_from(n).ok().map(Element::from),
+        (false, _) => u128::try_from(n).ok().map(Element::from)
+    }
+}
+
+/// Converts a number to the number type with the name, like a `u8` to `u16`. Integers are
+/// converted if they fit into the type, and all numbers can become floats like in inferred arrays.
+/// Returns `None` if the value is not a number or does not fit into the type.
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+pub(crate) fn convert_number(value: &Element, type_name: &str) -> Option<Element> {
+    let number = number(value, "").ok()??;
+    match type_name {
+        "f32" => Some(Element::PmlF32(number.as_f64() as f32)).filter(|_| !matches!(value, Element::PmlF64(_))),
+        "f64" => Some(Element::PmlF64(number.as_f64())),
+        _ => match number {
+            Number::Int(n) => {
+                let signed = type_name.starts_with('i');
+                let bits = type_name.strip_prefix(['i', 'u'])?.parse().ok()?;
+                typed_integer(n, Some(IntType { signed, bits }))
+            }
+            Number::Float(_) => None
+        }
+    }
+}
+
+/// Returns the integer with the smallest type it fits into, like a literal without forced type.
+pub(crate) fn integer(n: i128) -> Element {
+    if n < 0 {
+        i8::try_from(n).map(Element::from)
+            .or_else(|_| i16::try_from(n).map(Element::from))
+            .or_else(|_| i32::try_from(n).map(Element::from))
+            .or_else(|_| i64::try_from(n).map(Element::from))
+            .unwrap_or(Element::PmlI128(n))
+    }
+    else {
+        u8::try_from(n).map(Element::from)
+            .or_else(|_| u16::try_from(n).map(Element::from))
+            .or_else(|_| u32::try_from(n).map(Element::from))
+            .or_else(|_| u64::try_from(n).map(Element::from))
+            .unwrap_or(Element::PmlU128(n.unsigned_abs()))
+    }
+}
+
+fn invalid_operands(key: &str, operation: String) -> Error {
+    Error::InvalidOperands {
+        key: key.to_string(),
+        operation
+    }
+}
+
+fn overflow(key: &str) -> Error {
+    Error::ArithmeticOverflow {
+        key: key.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, Element, ParseError};
+
+    fn value(input: &str, key: &str) -> Element {
+        let config = parse::string(input).expect("The input should be valid");
+        config.elements[key].clone()
+    }
+
+    fn overflows(input: &str) -> bool {
+        matches!(parse::string(input), Err(ParseError::ArithmeticOverflow{..}))
+    }
+
+    #[test]
+    fn keeps_type_of_typed_operands() {
+        assert!(matches!(value("a = (<u8> 200 + <u8> 50);", "a"), Element::PmlU8(250)));
+        assert!(matches!(value("a = (<u8> 200 + <i8> 1);", "a"), Element::PmlI16(201)));
+        assert!(matches!(value("a = (<u16> 3 * 4);", "a"), Element::PmlU16(12)));
+    }
+
+    #[test]
+    fn widens_inferred_numbers() {
+        assert!(matches!(value("x = 100; y = |x| * 4;", "y"), Element::PmlU16(400)));
+    }
+
+    #[test]
+    fn reports_overflow() {
+        assert!(overflows("a = (<u8> 200 + <u8> 56);"));
+        assert!(overflows("a = (<u8> 1 - <u8> 2);"));
+        assert!(overflows("a = (-(<i8> -128));"));
+        assert!(overflows("x = 100; y = |x| * 4; z = |y| + <u8> 1;"));
+    }
+
+    #[test]
+    fn reports_mismatching_types() {
+        match parse::string("a = (1 + \"x\");") {
+            Err(ParseError::InvalidOperands{key, operation}) => {
+                assert_eq!(key, "a");
+                assert_eq!(operation, "u8 + str");
+            }
+            res => panic!("Expected invalid operands, got {res:?}")
+        }
+        assert!(matches!(parse::string("a = (true * 2);"), Err(ParseError::InvalidOperands{..})));
+    }
+
+    #[test]
+    fn reports_division_by_zero() {
+        for input in ["a = (<i32> 1 / 0);", "a = 7 % 0;", "z = 0; a = 1 / |z|;"] {
+            assert!(matches!(parse::string(input), Err(ParseError::DivisionByZero{ref key}) if key == "a"), "{input}");
+        }
+        assert!(matches!(value("a = 1.0 / 0;", "a"), Element::PmlF32(f) if f.is_infinite()));
+    }
+
+    #[test]
+    fn names_bool_in_logical_errors() {
+        match parse::string("a = (true && 1);") {
+            Err(ParseError::InvalidOperands{operation, ..}) => assert_eq!(operation, "bool && u8"),
+            res => panic!("Expected invalid operands, got {res:?}")
+        }
+    }
+
+    #[test]
+    fn rejects_forced_types_without_number() {
+        let error = parse::string("a = [1]; b = |a| + <u8> [2];").unwrap_err();
+        assert!(matches!(error, ParseError::IllegalCharacter{..}), "{error:?}");
+    }
+
+    #[test]
+    fn recognizes_expressions_starting_with_literals() {
+        assert!(matches!(value("w = 3; a = 2 * |w|;", "a"), Element::PmlU8(6)));
+        assert!(matches!(value("w = \"y\"; a = \"x\" + |w|;", "a"), Element::PmlString(s) if s == "xy"));
+        assert!(matches!(value("f = false; a = true && |f|;", "a"), Element::PmlBool(false)));
+        assert!(matches!(value("a = 1 + 2;", "a"), Element::PmlU8(3)));
+        assert!(matches!(value("a = 5; b = -|a|;", "b"), Element::PmlI8(-5)));
+        assert!(matches!(value("a = [1 + 2, 3];", "a"), Element::PmlArray(_)));
+    }
+
+    #[test]
+    fn keeps_literals_that_are_not_expressions() {
+        assert!(matches!(value("a = -5;", "a"), Element::PmlI8(-5)));
+        assert!(matches!(value("a = 2024-01-01;", "a"), Element::PmlDate(_)));
+        assert!(matches!(value("b = \"y\"; a = \"x\" |b|;", "a"), Element::PmlString(s) if s == "xy"));
+        assert!(matches!(value("a = \"x\" <<EOF\n  y\n  EOF;", "a"), Element::PmlString(s) if s == "xy"));
+    }
+}
@@ -1,5 +1,73 @@
-use crate::{ParseError as Error, parse::{ParseData, TerminatorType}};
-use super::{get_number_type_and_string, WIPResult};
+use crate::{ParseError as Error, parse::{ParseData, TerminatorType, WIPElement, WIPArrayItem, ForcedArrayType}, Element, ArrayElement};
+use super::{get_number_string, illegal_char_err, WIPResult, ForceCategory};
+use super::ForceCategory::{I8, I16, I32, I64, I128, U8, U16, U32, U64, U128, F32, F64, Bool, Struct, FString, FChar, FBytes, FEnum};
+#[cfg(feature = "bignum")]
+use super::ForceCategory::{FBigInt, FDecimal};
+
+/// Parses an array with a forced type, after its opening bracket. Nested arrays all use that type
+/// for their values.
+pub(super) fn typed(parse_data: &mut ParseData, force_type: ForceCategory) -> WIPResult {
+    if parse_data.next_non_whitespace_peek() == Some('[') {
+        return nested(parse_data, force_type);
+    }
+    match force_type {
+        I8 => i8(parse_data),
+        I16 => i16(parse_data),
+        I32 => i32(parse_data),
+        I64 => i64(parse_data),
+        I128 => i128(parse_data),
+        U8 => u8(parse_data),
+        U16 => u16(parse_data),
+        U32 => u32(parse_data),
+        U64 => u64(parse_data),
+        U128 => u128(parse_data),
+        F32 => f32(parse_data),
+        F64 => f64(parse_data),
+        Bool => bool(parse_data),
+        Struct => structs(parse_data),
+        FString => strings(parse_data),
+        FChar => mixed(parse_data, force_type, |parse_data| Ok(super::char(parse_data, TerminatorType::Array)?.into())),
+        FBytes => mixed(parse_data, force_type, |parse_data| Ok(Element::PmlBytes(super::bytes(parse_data, TerminatorType::Array)?))),
+        FEnum(index) => mixed(parse_data, force_type, |parse_data| {
+            let value = super::token(parse_data, TerminatorType::Array)?;
+            super::enum_variant(parse_data, index, value)
+        }),
+        #[cfg(feature = "bignum")]
+        FBigInt | FDecimal => mixed(parse_data, force_type, |parse_data| {
+            let (literal, string) = get_number_string(parse_data, TerminatorType::Array)?;
+            super::parse_forced_number(&string, force_type).map_err(|error| Error::ParseNumberError {
+                line: parse_data.line,
+                value: literal,
+                error
+            })
+        }),
+    }
+}
+
+fn nested(parse_data: &mut ParseData, force_type: ForceCategory) -> WIPResult {
+    let mut array = Vec::new();
+    loop {
+        match parse_data.next_non_whitespace_peek() {
+            Some(c) if is_deferred(parse_data, c) => return spread_rest(parse_data, force_type, array),
+            _ => ()
+        }
+        match parse_data.next_non_whitespace() {
+            Some('[') => (),
+            Some(']') => break,
+            Some(c) => return Err(illegal_char_err(c, parse_data)),
+            None => return Err(Error::UnexpectedEOF)
+        }
+        array.push(typed(parse_data, force_type)?.into());
+        parse_data.try_skip_comment();
+        match parse_data.next_non_whitespace() {
+            Some(',') => parse_data.try_skip_comment(),
+            Some(']') => break,
+            Some(c) => return Err(illegal_char_err(c, parse_data)),
+            None => return Err(Error::UnexpectedEOF)
+        }
+    }
+    Ok(WIPElement::Array(array))
+}
 
 pub(super) fn strings(parse_data: &mut ParseData) -> WIPResult {
     let mut array = Vec::new();
@@ -11,6 +79,10 @@ pub(super) fn strings(parse_data: &mut ParseData) -> WIPResult {
                 parse_data.next_char();
                 break;
             }
+            Some('.') if parse_data.upcoming("..") => {
+                let items = array.into_iter().map(|(_, string)| WIPArrayItem::String(string)).collect();
+                return spread_rest(parse_data, FString, items);
+            }
             Some(_) => ()
         }
         array.push((count, super::string(parse_data, TerminatorType::Array)?));
@@ -30,16 +102,36 @@ pub(super) fn structs(parse_data: &mut ParseData) -> WIPResult {
                 parse_data.next_char();
                 break;
             }
+            Some('.') if parse_data.upcoming("..") => {
+                let items = array.into_iter().map(|(_, s)| WIPArrayItem::Struct(s)).collect();
+                return spread_rest(parse_data, Struct, items);
+            }
             Some(_) => ()
         }
         array.push((count, super::pml_struct(parse_data, TerminatorType::Array)?));
-        parse_data.drop_last_nested_ref();
         count += 1;
         parse_data.try_skip_comment();
     }
     Ok(array.into())
 }
 
+pub(super) fn untyped(parse_data: &mut ParseData) -> WIPResult {
+    let mut array = Vec::new();
+    while parse_data.last_char != ']' {
+        match parse_data.next_non_whitespace_peek() {
+            None => return Err(Error::UnexpectedEOF),
+            Some(']') => {
+                parse_data.next_char();
+                break;
+            }
+            Some(_) => ()
+        }
+        array.push(super::array_item(parse_data, array.len())?);
+        parse_data.try_skip_comment();
+    }
+    Ok(WIPElement::Array(array))
+}
+
 pub(super) fn bool(parse_data: &mut ParseData) -> WIPResult {
     let mut array = Vec::new();
     while parse_data.last_char != ']' {
@@ -49,6 +141,7 @@ pub(super) fn bool(parse_data: &mut ParseData) -> WIPResult {
                 parse_data.next_char();
                 break;
             }
+            Some(c) if is_deferred(parse_data, c) => return spread_rest_from(parse_data, Bool, array),
             Some(_) => ()
         }
         array.push(super::bool(parse_data, TerminatorType::Array)?);
@@ -66,14 +159,15 @@ pub(super) fn f32(parse_data: &mut ParseData) -> WIPResult {
                 parse_data.next_char();
                 break;
             }
+            Some(c) if is_deferred(parse_data, c) => return spread_rest_from(parse_data, F32, array),
             Some(_) => ()
         }
-        let string = get_number_type_and_string(parse_data, TerminatorType::Array)?.1;
+        let (literal, string) = get_number_string(parse_data, TerminatorType::Array)?;
         match string.parse::<f32>() {
             Ok(num) => array.push(num),
             Err(e) => return Err(Error::ParseNumberError {
                 line: parse_data.line,
-                value: string,
+                value: literal,
                 error: e.into()
             })
         }
@@ -91,14 +185,15 @@ pub(super) fn f64(parse_data: &mut ParseData) -> WIPResult {
                 parse_data.next_char();
                 break;
             }
+            Some(c) if is_deferred(parse_data, c) => return spread_rest_from(parse_data, F64, array),
             Some(_) => ()
         }
-        let string = get_number_type_and_string(parse_data, TerminatorType::Array)?.1;
+        let (literal, string) = get_number_string(parse_data, TerminatorType::Array)?;
         match string.parse::<f64>() {
             Ok(num) => array.push(num),
             Err(e) => return Err(Error::ParseNumberError {
                 line: parse_data.line,
-                value: string,
+                value: literal,
                 error: e.into()
             })
         }
@@ -116,14 +211,15 @@ pub(super) fn i8(parse_data: &mut ParseData) -> WIPResult {
                 parse_data.next_char();
                 break;
             }
+            Some(c) if is_deferred(parse_data, c) => return spread_rest_from(parse_data, I8, array),
             Some(_) => ()
         }
-        let string = get_number_type_and_string(parse_data, TerminatorType::Array)?.1;
+        let (literal, string) = get_number_string(parse_data, TerminatorType::Array)?;
         match string.parse::<i8>() {
             Ok(num) => array.push(num),
             Err(e) => return Err(Error::ParseNumberError {
                 line: parse_data.line,
-                value: string,
+                value: literal,
                 error: e.into()
             })
         }
@@ -141,14 +237,15 @@ pub(super) fn i16(parse_data: &mut ParseData) -> WIPResult {
                 parse_data.next_char();
                 break;
             }
+            Some(c) if is_deferred(parse_data, c) => return spread_rest_from(parse_data, I16, array),
             Some(_) => ()
         }
-        let string = get_number_type_and_string(parse_data, TerminatorType::Array)?.1;
+        let (literal, string) = get_number_string(parse_data, TerminatorType::Array)?;
         match string.parse::<i16>() {
             Ok(num) => array.push(num),
             Err(e) => return Err(Error::ParseNumberError {
                 line: parse_data.line,
-                value: string,
+                value: literal,
                 error: e.into()
             })
         }
@@ -166,15 +263,16 @@ pub(super) fn i32(parse_data: &mut ParseData) -> WIPResult {
                 parse_data.next_char();
                 break;
             }
+            Some(c) if is_deferred(parse_data, c) => return spread_rest_from(parse_data, I32, array),
             Some(_) => ()
         }
         parse_data.next_non_whitespace_peek();
-        let string = get_number_type_and_string(parse_data, TerminatorType::Array)?.1;
+        let (literal, string) = get_number_string(parse_data, TerminatorType::Array)?;
         match string.parse::<i32>() {
             Ok(num) => array.push(num),
             Err(e) => return Err(Error::ParseNumberError {
                 line: parse_data.line,
-                value: string,
+                value: literal,
                 error: e.into()
             })
         }
@@ -192,14 +290,15 @@ pub(super) fn i64(parse_data: &mut ParseData) -> WIPResult {
                 parse_data.next_char();
                 break;
             }
+            Some(c) if is_deferred(parse_data, c) => return spread_rest_from(parse_data, I64, array),
             Some(_) => ()
         }
-        let string = get_number_type_and_string(parse_data, TerminatorType::Array)?.1;
+        let (literal, string) = get_number_string(parse_data, TerminatorType::Array)?;
         match string.parse::<i64>() {
             Ok(num) => array.push(num),
             Err(e) => return Err(Error::ParseNumberError {
                 line: parse_data.line,
-                value: string,
+                value: literal,
                 error: e.into()
             })
         }
@@ -217,14 +316,15 @@ pub(super) fn i128(parse_data: &mut ParseData) -> WIPResult {
                 parse_data.next_char();
                 break;
             }
+            Some(c) if is_deferred(parse_data, c) => return spread_rest_from(parse_data, I128, array),
             Some(_) => ()
         }
-        let string = get_number_type_and_string(parse_data, TerminatorType::Array)?.1;
+        let (literal, string) = get_number_string(parse_data, TerminatorType::Array)?;
         match string.parse::<i128>() {
             Ok(num) => array.push(num),
             Err(e) => return Err(Error::ParseNumberError {
                 line: parse_data.line,
-                value: string,
+                value: literal,
                 error: e.into()
             })
         }
@@ -242,14 +342,15 @@ pub(super) fn u8(parse_data: &mut ParseData) -> WIPResult {
                 parse_data.next_char();
                 break;
             }
+            Some(c) if is_deferred(parse_data, c) => return spread_rest_from(parse_data, U8, array),
             Some(_) => ()
         }
-        let string = get_number_type_and_string(parse_data, TerminatorType::Array)?.1;
+        let (literal, string) = get_number_string(parse_data, TerminatorType::Array)?;
         match string.parse::<u8>() {
             Ok(num) => array.push(num),
             Err(e) => return Err(Error::ParseNumberError {
                 line: parse_data.line,
-                value: string,
+                value: literal,
                 error: e.into()
             })
         }
@@ -267,14 +368,15 @@ pub(super) fn u16(parse_data: &mut ParseData) -> WIPResult {
                 parse_data.next_char();
                 break;
             }
+            Some(c) if is_deferred(parse_data, c) => return spread_rest_from(parse_data, U16, array),
             Some(_) => ()
         }
-        let string = get_number_type_and_string(parse_data, TerminatorType::Array)?.1;
+        let (literal, string) = get_number_string(parse_data, TerminatorType::Array)?;
         match string.parse::<u16>() {
             Ok(num) => array.push(num),
             Err(e) => return Err(Error::ParseNumberError {
                 line: parse_data.line,
-                value: string,
+                value: literal,
                 error: e.into()
             })
         }
@@ -292,14 +394,15 @@ pub(super) fn u32(parse_data: &mut ParseData) -> WIPResult {
                 parse_data.next_char();
                 break;
             }
+            Some(c) if is_deferred(parse_data, c) => return spread_rest_from(parse_data, U32, array),
             Some(_) => ()
         }
-        let string = get_number_type_and_string(parse_data, TerminatorType::Array)?.1;
+        let (literal, string) = get_number_string(parse_data, TerminatorType::Array)?;
         match string.parse::<u32>() {
             Ok(num) => array.push(num),
             Err(e) => return Err(Error::ParseNumberError {
                 line: parse_data.line,
-                value: string,
+                value: literal,
                 error: e.into()
             })
         }
@@ -317,14 +420,15 @@ pub(super) fn u64(parse_data: &mut ParseData) -> WIPResult {
                 parse_data.next_char();
                 break;
             }
+            Some(c) if is_deferred(parse_data, c) => return spread_rest_from(parse_data, U64, array),
             Some(_) => ()
         }
-        let string = get_number_type_and_string(parse_data, TerminatorType::Array)?.1;
+        let (literal, string) = get_number_string(parse_data, TerminatorType::Array)?;
         match string.parse::<u64>() {
             Ok(num) => array.push(num),
             Err(e) => return Err(Error::ParseNumberError {
                 line: parse_data.line,
-                value: string,
+                value: literal,
                 error: e.into()
             })
         }
@@ -342,14 +446,15 @@ pub(super) fn u128(parse_data: &mut ParseData) -> WIPResult {
                 parse_data.next_char();
                 break;
             }
+            Some(c) if is_deferred(parse_data, c) => return spread_rest_from(parse_data, U128, array),
             Some(_) => ()
         }
-        let string = get_number_type_and_string(parse_data, TerminatorType::Array)?.1;
+        let (literal, string) = get_number_string(parse_data, TerminatorType::Array)?;
         match string.parse::<u128>() {
             Ok(num) => array.push(num),
             Err(e) => return Err(Error::ParseNumberError {
                 line: parse_data.line,
-                value: string,
+                value: literal,
                 error: e.into()
             })
         }
@@ -357,3 +462,102 @@ pub(super) fn u128(parse_data: &mut ParseData) -> WIPResult {
     }
     Ok(array.into())
 }
+
+/// Parses an array of values that can not be part of arrays with a single type, like chars, so
+/// the result is a `PmlMixed` array.
+fn mixed(parse_data: &mut ParseData, force_type: ForceCategory, value: impl Fn(&mut ParseData) -> Result<Element, Error>) -> WIPResult {
+    let mut array = Vec::new();
+    while parse_data.last_char != ']' {
+        match parse_data.next_non_whitespace_peek() {
+            None => return Err(Error::UnexpectedEOF),
+            Some(']') => {
+                parse_data.next_char();
+                break;
+            }
+            Some(c) if is_deferred(parse_data, c) => {
+                let items = array.into_iter().map(WIPArrayItem::Element).collect();
+                return spread_rest(parse_data, force_type, items);
+            }
+            Some(_) => ()
+        }
+        array.push(value(parse_data)?);
+        parse_data.try_skip_comment();
+    }
+    Ok(Element::PmlArray(ArrayElement::PmlMixed(array)).into())
+}
+
+/// Checks if the next value of a typed array can only be added once it is resolved, because it is
+/// a spread like `..|hosts|` or an expression like `|port|` or `|port| + 1`.
+fn is_deferred(parse_data: &ParseData, c: char) -> bool {
+    parse_data.upcoming("..") || matches!(c, '|' | '(' | '!')
+        || (c.is_ascii_alphabetic() && super::is_function_call(parse_data))
+        || super::is_expression(parse_data)
+}
+
+/// Returns an empty array whose values have the forced type.
+fn empty(force_type: ForceCategory) -> ArrayElement {
+    match force_type {
+        I8 => ArrayElement::PmlI8(Vec::new()),
+        I16 => ArrayElement::PmlI16(Vec::new()),
+        I32 => ArrayElement::PmlI32(Vec::new()),
+        I64 => ArrayElement::PmlI64(Vec::new()),
+        I128 => ArrayElement::PmlI128(Vec::new()),
+        U8 => ArrayElement::PmlU8(Vec::new()),
+        U16 => ArrayElement::PmlU16(Vec::new()),
+        U32 => ArrayElement::PmlU32(Vec::new()),
+        U64 => ArrayElement::PmlU64(Vec::new()),
+        U128 => ArrayElement::PmlU128(Vec::new()),
+        F32 => ArrayElement::PmlF32(Vec::new()),
+        F64 => ArrayElement::PmlF64(Vec::new()),
+        Bool => ArrayElement::PmlBool(Vec::new()),
+        Struct => ArrayElement::PmlStruct(Vec::new()),
+        FString => ArrayElement::PmlString(Vec::new()),
+        _ => ArrayElement::PmlMixed(Vec::new())
+    }
+}
+
+/// Parses the rest of a typed array once it contains a value that has to be resolved first, like
+/// the spread `..|hosts|` or the variable `|port|`. The values before are passed as items. Nested
+/// arrays may follow if the items are nested arrays. All values are checked against the type once
+/// they are resolved.
+fn spread_rest(parse_data: &mut ParseData, force_type: ForceCategory, mut items: Vec<WIPArrayItem>) -> WIPResult {
+    let forced_type = ForcedArrayType {
+        key: parse_data.get_full_struct_path(),
+        name: super::force_type_name(parse_data, force_type),
+        empty: empty(force_type)
+    };
+    while parse_data.last_char != ']' {
+        match parse_data.next_non_whitespace_peek() {
+            None => return Err(Error::UnexpectedEOF),
+            Some(']') => {
+                parse_data.next_char();
+                break;
+            }
+            Some('.') if parse_data.upcoming("..") => items.push(super::spread(parse_data)?),
+            Some('[') => {
+                parse_data.next_char();
+                items.push(typed(parse_data, force_type)?.into());
+                parse_data.try_skip_comment();
+                match parse_data.next_non_whitespace() {
+                    Some(',' | ']') => (),
+                    Some(c) => return Err(illegal_char_err(c, parse_data)),
+                    None => return Err(Error::UnexpectedEOF)
+                }
+            }
+            Some(c) if ![FString, Struct].contains(&force_type) && is_deferred(parse_data, c) => items.push(super::typed_item(parse_data)?),
+            Some(_) => {
+                let key = items.len().to_string();
+                items.push(super::forced_value(parse_data, TerminatorType::Array, &key, force_type, false)?.into());
+            }
+        }
+        parse_data.try_skip_comment();
+    }
+    Ok(WIPElement::TypedArray(items, forced_type))
+}
+
+/// Like [`spread_rest`], for the values parsed so far of an array with a single type.
+fn spread_rest_from<T>(parse_data: &mut ParseData, force_type: ForceCategory, values: Vec<T>) -> WIPResult
+where Element: From<T> {
+    let items = values.into_iter().map(|value| WIPArrayItem::Element(value.into())).collect();
+    spread_rest(parse_data, force_type, items)
+}
@@ -0,0 +1,324 @@
+use crate::{ParseError as Error, parse::{ParseData, TerminatorType, WIPElement, WIPArrayItem, ISElem, Expression, Expr, Call, Operator, Extension}, Element};
+use super::{illegal_char_err, WIPResult};
+
+/// Parses a value that starts with `|`, `!`, `(`, a function call or a
+/// [literal that is followed by an operator](is_expression). It is an expression like
+/// `|workers| * 4`, unless it starts with a variable that is not followed by an operator. Then it
+/// is a string.
+pub(in crate::parse) fn expression_or_string(parse_data: &mut ParseData, terminator_type: TerminatorType) -> WIPResult {
+    let terminators = match terminator_type {
+        TerminatorType::Struct => vec![';'],
+        TerminatorType::Array => vec![',', ']']
+    };
+    let first = match parse_data.next_non_whitespace_peek() {
+        Some('|') => {
+            parse_data.next_char();
+            let mut string_elements = Vec::new();
+            super::string_insert_variable(parse_data, &mut string_elements)?;
+            parse_data.try_skip_comment();
+            let next = parse_data.next_non_whitespace_peek();
+            let is_string = next.is_some_and(|c| super::is_string_start(parse_data, c)) && !parse_data.upcoming("||");
+            if is_string || (next_operator(parse_data).is_none() && next != Some('?')) {
+                return Ok(super::string_with(parse_data, terminator_type, string_elements)?.into());
+            }
+            if next_operator(parse_data) == Some(Operator::Add) && parse_data.peek_non_whitespace_after(1) == Some('{') {
+                return extension(parse_data, terminator_type, string_elements);
+            }
+            variable(parse_data, string_elements)?
+        }
+        _ => operand(parse_data)?
+    };
+    let expr = binary(parse_data, first, 0)?;
+    let expr = conditional(parse_data, expr)?;
+    parse_data.try_skip_comment();
+    match parse_data.next_non_whitespace() {
+        Some(c) if terminators.contains(&c) => Ok(WIPElement::Expression(Expression {
+            key: parse_data.get_full_struct_path(),
+            expr
+        })),
+        Some(c) => Err(illegal_char_err(c, parse_data)),
+        None => Err(Error::UnexpectedEOF)
+    }
+}
+
+/// Parses a struct that extends another one, like `|worker_a| + { threads = 8; }`, after the
+/// variable that references the base struct. The struct contains all values of the base struct
+/// that it does not override.
+fn extension(parse_data: &mut ParseData, terminator_type: TerminatorType, string_elements: Vec<ISElem>) -> WIPResult {
+    let Expr::Variable(link, name) = variable(parse_data, string_elements)? else {
+        return Err(illegal_char_err('+', parse_data));
+    };
+    parse_data.next_char();
+    parse_data.next_non_whitespace_peek();
+    let wip_struct = super::pml_struct(parse_data, terminator_type)?;
+    wip_struct.borrow_mut().extends = Some(Extension {
+        key: parse_data.get_full_struct_path(),
+        link,
+        name
+    });
+    Ok(WIPElement::Struct(wip_struct))
+}
+
+/// Parses an item like `..|common.hosts|` of an array, which is replaced by the values of the
+/// array it evaluates to.
+pub(in crate::parse) fn spread(parse_data: &mut ParseData) -> Result<WIPArrayItem, Error> {
+    parse_data.skip_chars(2);
+    let expr = expression(parse_data)?;
+    parse_data.try_skip_comment();
+    match parse_data.next_non_whitespace() {
+        Some(',' | ']') => Ok(WIPArrayItem::Spread(Expression {
+            key: parse_data.get_full_struct_path(),
+            expr
+        })),
+        Some(c) => Err(illegal_char_err(c, parse_data)),
+        None => Err(Error::UnexpectedEOF)
+    }
+}
+
+/// Parses an expression like `|port|` or `|port| + 1` that is a value of an array with a forced
+/// type. Unlike in other values, a single variable is not a string but the value it references.
+pub(in crate::parse) fn typed_item(parse_data: &mut ParseData) -> Result<WIPArrayItem, Error> {
+    let expr = expression(parse_data)?;
+    parse_data.try_skip_comment();
+    match parse_data.next_non_whitespace() {
+        Some(',' | ']') => Ok(WIPArrayItem::Expression(Expression {
+            key: parse_data.get_full_struct_path(),
+            expr
+        })),
+        Some(c) => Err(illegal_char_err(c, parse_data)),
+        None => Err(Error::UnexpectedEOF)
+    }
+}
+
+fn expression(parse_data: &mut ParseData) -> Result<Expr, Error> {
+    let first = operand(parse_data)?;
+    let expr = binary(parse_data, first, 0)?;
+    conditional(parse_data, expr)
+}
+
+/// Parses the operators and operands after the first operand, as long as the operators bind at
+/// least as strong as the minimal precedence.
+fn binary(parse_data: &mut ParseData, mut left: Expr, min_precedence: u8) -> Result<Expr, Error> {
+    while let Some(op) = next_operator(parse_data).filter(|op| op.precedence() >= min_precedence) {
+        parse_data.skip_chars(op.symbol().len());
+        let mut right = operand(parse_data)?;
+        while let Some(next) = next_operator(parse_data).filter(|next| next.precedence() > op.precedence()) {
+            right = binary(parse_data, right, next.precedence())?;
+        }
+        left = Expr::Binary(op, Box::new(left), Box::new(right));
+    }
+    Ok(left)
+}
+
+/// Parses `? then : otherwise` after the condition, if there is one.
+fn conditional(parse_data: &mut ParseData, condition: Expr) -> Result<Expr, Error> {
+    parse_data.try_skip_comment();
+    if parse_data.next_non_whitespace_peek() != Some('?') {
+        return Ok(condition);
+    }
+    parse_data.next_char();
+    let then = expression(parse_data)?;
+    parse_data.try_skip_comment();
+    match parse_data.next_non_whitespace() {
+        Some(':') => (),
+        Some(c) => return Err(illegal_char_err(c, parse_data)),
+        None => return Err(Error::UnexpectedEOF)
+    }
+    let otherwise = expression(parse_data)?;
+    Ok(Expr::Conditional(Box::new(condition), Box::new(then), Box::new(otherwise)))
+}
+
+/// Returns the operator that follows, without moving past it.
+fn next_operator(parse_data: &mut ParseData) -> Option<Operator> {
+    parse_data.try_skip_comment();
+    parse_data.next_non_whitespace_peek()?;
+    Operator::ALL.into_iter().find(|op| parse_data.upcoming(op.symbol()))
+}
+
+fn operand(parse_data: &mut ParseData) -> Result<Expr, Error> {
+    parse_data.try_skip_comment();
+    match parse_data.next_non_whitespace_peek() {
+        Some('|') => {
+            parse_data.next_char();
+            let mut string_elements = Vec::new();
+            super::string_insert_variable(parse_data, &mut string_elements)?;
+            variable(parse_data, string_elements)
+        }
+        Some('"') => {
+            parse_data.next_char();
+            let mut string_elements = Vec::new();
+            super::string_insert_literal(parse_data, &mut string_elements)?;
+            match string_elements.pop() {
+                Some(ISElem::Literal(value)) => Ok(Expr::Value(Element::PmlString(value))),
+                _ => unreachable!("A literal should always be inserted")
+            }
+        }
+        Some('(') => {
+            parse_data.next_char();
+            let expr = expression(parse_data)?;
+            parse_data.try_skip_comment();
+            match parse_data.next_non_whitespace() {
+                Some(')') => Ok(expr),
+                Some(c) => Err(illegal_char_err(c, parse_data)),
+                None => Err(Error::UnexpectedEOF)
+            }
+        }
+        Some('[') => {
+            parse_data.next_char();
+            array(parse_data)
+        }
+        Some('!') => {
+            parse_data.next_char();
+            Ok(Expr::Not(Box::new(operand(parse_data)?)))
+        }
+        Some('-') => {
+            parse_data.next_char();
+            Ok(Expr::Negate(Box::new(operand(parse_data)?)))
+        }
+        Some('<') => {
+            parse_data.next_char();
+            let force_type = super::number_force_category(parse_data)?;
+            parse_data.try_skip_comment();
+            parse_data.next_non_whitespace_peek();
+            let value = literal(parse_data);
+            if value.is_empty() {
+                return match parse_data.next_non_whitespace() {
+                    Some(c) => Err(illegal_char_err(c, parse_data)),
+                    None => Err(Error::UnexpectedEOF)
+                };
+            }
+            Ok(Expr::Value(super::forced_number(value, force_type, parse_data.line)?))
+        }
+        Some(c) if c.is_ascii_alphanumeric() || c == '.' => {
+            let value = literal(parse_data);
+            match c {
+                c if c.is_ascii_digit() || c == '.' => Ok(Expr::Number(super::number_literal(value, parse_data.line)?)),
+                _ if parse_data.peek() == Some('(') => call(parse_data, value),
+                _ => Ok(Expr::Value(super::word_literal(value, parse_data.line)?))
+            }
+        }
+        Some(c) => Err(illegal_char_err(c, parse_data)),
+        None => Err(Error::UnexpectedEOF)
+    }
+}
+
+/// Reads a number or word in an expression, like `1.5e-3`, `0xFF` or `true`. A sign is only
+/// read at the start and after the exponent of a decimal number.
+fn literal(parse_data: &mut ParseData) -> String {
+    let mut value = String::new();
+    while let Some(c) = parse_data.peek() {
+        let is_exponent_sign = ['-', '+'].contains(&c) && value.ends_with(['e', 'E'])
+            && value.starts_with(|c: char| c.is_ascii_digit() || c == '.') && !value.starts_with("0x");
+        let is_sign = ['-', '+'].contains(&c) && value.is_empty();
+        if !(c.is_ascii_alphanumeric() || ['.', '_'].contains(&c) || is_exponent_sign || is_sign) {
+            break;
+        }
+        value.push(c);
+        parse_data.next_char();
+    }
+    value
+}
+
+/// Checks if the value is an expression that starts with a literal followed by an operator, like
+/// `2 * |workers|`, `"x" + |name|` or `true && |enabled|`, or with a negated variable or group like
+/// `-|offset|`. The operator has to be on the same line as the literal.
+pub(in crate::parse) fn is_expression(parse_data: &ParseData) -> bool {
+    let line = parse_data.peek_line();
+    let line = line.trim_start();
+    let rest = if let Some(negated) = line.strip_prefix('-') {
+        if negated.trim_start().starts_with(['|', '(']) {
+            return true;
+        }
+        negated.trim_start_matches(is_token_char)
+    }
+    else if let Some(string) = line.strip_prefix('"') {
+        if string.starts_with("\"\"") {
+            return false;
+        }
+        let mut escape = false;
+        let Some(end) = string.find(|c| {
+            let is_end = c == '"' && !escape;
+            escape = c == '\\' && !escape;
+            is_end
+        }) else {
+            return false;
+        };
+        &string[end + 1..]
+    }
+    else {
+        line.trim_start_matches(is_token_char)
+    };
+    let rest = rest.trim_start();
+    rest.len() < line.len() && !rest.starts_with("<<") && Operator::ALL.into_iter().any(|op| rest.starts_with(op.symbol()))
+}
+
+/// Checks if the char can be part of a number or word without quotes, like `1.5e-3` or `true`.
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || ['-', '+', '.', ':', '_'].contains(&c)
+}
+
+/// Checks if the value is a call of a function like `upper(|name|)`.
+pub(in crate::parse) fn is_function_call(parse_data: &ParseData) -> bool {
+    let line = parse_data.peek_line();
+    let rest = line.trim_start().trim_start_matches(|c: char| c.is_ascii_alphanumeric() || c == '_');
+    rest.starts_with('(')
+}
+
+/// Parses the arguments of a function call, after the name of the function.
+fn call(parse_data: &mut ParseData, name: String) -> Result<Expr, Error> {
+    let Some((signature, function)) = parse_data.functions.get(&name) else {
+        return Err(Error::UnknownFunction {
+            line: parse_data.line,
+            name
+        });
+    };
+    parse_data.next_char();
+    let mut args = Vec::new();
+    parse_data.try_skip_comment();
+    if parse_data.next_non_whitespace_peek() == Some(')') {
+        parse_data.next_char();
+    }
+    else {
+        loop {
+            args.push(expression(parse_data)?);
+            parse_data.try_skip_comment();
+            match parse_data.next_non_whitespace() {
+                Some(',') => (),
+                Some(')') => break,
+                Some(c) => return Err(illegal_char_err(c, parse_data)),
+                None => return Err(Error::UnexpectedEOF)
+            }
+        }
+    }
+    Ok(Expr::Call(Call{name, signature, function, args}))
+}
+
+/// Parses an array like `[8080, |port|]` in an expression, after its opening bracket.
+fn array(parse_data: &mut ParseData) -> Result<Expr, Error> {
+    let mut items = Vec::new();
+    parse_data.try_skip_comment();
+    if parse_data.next_non_whitespace_peek() == Some(']') {
+        parse_data.next_char();
+        return Ok(Expr::Array(items));
+    }
+    loop {
+        items.push(expression(parse_data)?);
+        parse_data.try_skip_comment();
+        match parse_data.next_non_whitespace() {
+            Some(',') => (),
+            Some(']') => return Ok(Expr::Array(items)),
+            Some(c) => return Err(illegal_char_err(c, parse_data)),
+            None => return Err(Error::UnexpectedEOF)
+        }
+    }
+}
+
+/// Turns the parts of an interpolation like `|workers|` into an operand. It has to contain
+/// exactly one variable without format spec.
+fn variable(parse_data: &ParseData, mut string_elements: Vec<ISElem>) -> Result<Expr, Error> {
+    match (string_elements.pop(), string_elements.is_empty()) {
+        (Some(ISElem::Variable(link, name, None)), true) => Ok(Expr::Variable(link, name)),
+        (Some(ISElem::Literal(value)), true) => Ok(Expr::Value(Element::PmlString(value))),
+        _ => Err(illegal_char_err('|', parse_data))
+    }
+}
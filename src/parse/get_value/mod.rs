@@ -1,8 +1,12 @@
 mod arrays;
+mod expressions;
 
 use std::{cell::RefCell, rc::Rc};
-use crate::{elem::Element, errors::{ParseError as Error, ParseNumberError}};
-use super::{ParseData, KeyType, illegal_char_err, is_char_reserved, WIPElement, TerminatorType, ISElem, WIPStruct};
+use crate::{elem::Element, errors::{ParseError as Error, ParseNumberError}, datetime, units, encoding, enums::EnumValue};
+#[cfg(feature = "bignum")]
+use crate::bignum::{BigInt, Decimal};
+pub(super) use expressions::{expression_or_string, is_expression, is_function_call, spread, typed_item};
+use super::{ParseData, KeyType, illegal_char_err, is_char_reserved, WIPElement, WIPArrayItem, TerminatorType, ISElem, FormatSpec, WIPStruct};
 
 type StdResult = Result<Element, Error>;
 type WIPResult = Result<WIPElement, Error>;
@@ -39,18 +43,35 @@ enum ForceCategory {
     Bool,
     Struct,
     FString,
+    FChar,
+    FBytes,
+    /// An enum declared in the file, with its index in the meta info.
+    FEnum(usize),
+    #[cfg(feature = "bignum")]
+    FBigInt,
+    #[cfg(feature = "bignum")]
+    FDecimal,
 }
-use ForceCategory::{I8, I16, I32, I64, I128, U8, U16, U32, U64, U128, F32, F64, Bool, Struct, FString};
+use ForceCategory::{I8, I16, I32, I64, I128, U8, U16, U32, U64, U128, F32, F64, Bool, Struct, FString, FChar, FBytes, FEnum};
+#[cfg(feature = "bignum")]
+use ForceCategory::{FBigInt, FDecimal};
 
 pub(super) fn string(parse_data: &mut ParseData, terminator_type: TerminatorType) -> Result<Vec<ISElem>, Error> {
+    string_with(parse_data, terminator_type, Vec::new())
+}
+
+/// Parses the rest of a string whose first parts were parsed already.
+fn string_with(parse_data: &mut ParseData, terminator_type: TerminatorType, mut string_elements: Vec<ISElem>) -> Result<Vec<ISElem>, Error> {
     let terminators = match terminator_type {
         TerminatorType::Struct => vec![';'],
         TerminatorType::Array => vec![',', ']']
     };
-    let mut string_elements = Vec::new();
     while let Some(c) = parse_data.next_non_whitespace() {
         match c {
+            '"' if parse_data.skip_upcoming("\"\"") => string_insert_multi_line(parse_data, &mut string_elements)?,
             '"' => string_insert_literal(parse_data, &mut string_elements)?,
+            'r' => string_insert_raw(parse_data, &mut string_elements)?,
+            '<' if parse_data.skip_upcoming("<") => string_insert_heredoc(parse_data, &mut string_elements)?,
             '|' => string_insert_variable(parse_data, &mut string_elements)?,
             '#' => parse_data.skip_comment(),
             c if terminators.contains(&c) => return Ok(string_elements),
@@ -61,22 +82,52 @@ pub(super) fn string(parse_data: &mut ParseData, terminator_type: TerminatorType
 }
 
 fn string_insert_literal(parse_data: &mut ParseData, string_elements: &mut Vec<ISElem>) -> Result<(), Error> {
+    let mut value = String::new();
+    while let Some(c) = parse_data.next_char() {
+        match c {
+            '\\' => value.push(super::next_escape_sequence(parse_data)?),
+            '"' => {
+                string_elements.push(ISElem::Literal(value));
+                return Ok(());
+            }
+            c => value.push(c)
+        }
+    }
+    Err(Error::UnexpectedEOF)
+}
+
+/// Inserts a string in triple quotes, which may span multiple lines. The line break after the
+/// opening quotes, the indentation of the closing quotes and the indentation all lines have in
+/// common are removed.
+fn string_insert_multi_line(parse_data: &mut ParseData, string_elements: &mut Vec<ISElem>) -> Result<(), Error> {
     let mut escape = false;
+    let mut escape_positions = Vec::new();
     let mut value = String::new();
     while let Some(c) = parse_data.next_char() {
         match c {
             c if escape => {
                 escape = false;
-                value.push(match c{
-                    'r' => continue,
-                    'n' => '\n',
-                    't' => '\t',
-                    c => c
-                });
+                value.push('\\');
+                value.push(c);
             }
-            '\\' => escape = true,
-            '"' => {
-                string_elements.push(ISElem::Literal(value));
+            '\\' => {
+                escape = true;
+                escape_positions.push((parse_data.line, parse_data.column));
+            }
+            '"' if parse_data.skip_upcoming("\"\"") => {
+                let mut unescaped = String::new();
+                let mut escape_positions = escape_positions.into_iter();
+                let mut chars = strip_indentation(&value).into_iter();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            let (line, col) = escape_positions.next().expect("The position of every escape sequence should be known.");
+                            unescaped.push(super::escape_sequence(&mut chars, line, col)?);
+                        }
+                        c => unescaped.push(c)
+                    }
+                }
+                string_elements.push(ISElem::Literal(unescaped));
                 return Ok(());
             }
             c => value.push(c)
@@ -85,6 +136,101 @@ fn string_insert_literal(parse_data: &mut ParseData, string_elements: &mut Vec<I
     Err(Error::UnexpectedEOF)
 }
 
+/// Removes the line break at the start, the last line if it only contains whitespace, and the
+/// indentation all other lines have in common. Lines only share indentation that consists of the
+/// same whitespace characters.
+fn strip_indentation(value: &str) -> Vec<char> {
+    let value = value.strip_prefix("\r\n").or_else(|| value.strip_prefix('\n')).unwrap_or(value);
+    let mut lines: Vec<&str> = value.split('\n').collect();
+    if lines.len() > 1 && lines.last().is_some_and(|l| l.trim().is_empty()) {
+        lines.pop();
+    }
+    let indentation = lines.iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| &l[..l.len() - l.trim_start().len()])
+        .reduce(common_prefix)
+        .unwrap_or("");
+    let lines: Vec<&str> = lines.iter().map(|l| l.strip_prefix(indentation).unwrap_or(l.trim_start())).collect();
+    lines.join("\n").chars().collect()
+}
+
+/// Returns the longest prefix of both strings. Whitespace is compared literally, so a tab does not
+/// match spaces.
+fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str {
+    let len = a.char_indices()
+        .zip(b.chars())
+        .find(|((_, x), y)| x != y)
+        .map_or_else(|| a.len().min(b.len()), |((i, _), _)| i);
+    &a[..len]
+}
+
+/// Inserts a raw string like `r"C:\path"` or `r#"say "hi""#`, which has no escapes. The closing
+/// quote has to be followed by as many `#` as the opening one follows.
+fn string_insert_raw(parse_data: &mut ParseData, string_elements: &mut Vec<ISElem>) -> Result<(), Error> {
+    let mut hashes = 0;
+    loop {
+        match parse_data.next_char() {
+            Some('#') => hashes += 1,
+            Some('"') => break,
+            Some(c) => return Err(illegal_char_err(c, parse_data)),
+            None => return Err(Error::UnexpectedEOF)
+        }
+    }
+    let closing = "#".repeat(hashes);
+    let mut value = String::new();
+    while let Some(c) = parse_data.next_char() {
+        if c == '"' && parse_data.skip_upcoming(&closing) {
+            string_elements.push(ISElem::Literal(value));
+            return Ok(());
+        }
+        value.push(c);
+    }
+    Err(Error::UnexpectedEOF)
+}
+
+/// Inserts a heredoc like `<<PEM`, whose content follows on the next lines until a line that only
+/// contains `PEM`, optionally followed by the end of the value or the next part of the string. The
+/// content has no escapes, and the indentation of the closing line is removed from all lines.
+fn string_insert_heredoc(parse_data: &mut ParseData, string_elements: &mut Vec<ISElem>) -> Result<(), Error> {
+    let is_tag_char = |c: char| c.is_alphanumeric() || c == '_';
+    let tag = parse_data.rest_of_line();
+    let tag = tag.trim();
+    if let Some(c) = tag.chars().find(|c| !is_tag_char(*c)) {
+        return Err(illegal_char_err(c, parse_data));
+    }
+    if tag.is_empty() {
+        return Err(illegal_char_err('\n', parse_data));
+    }
+    let mut lines = Vec::new();
+    while parse_data.has_next() {
+        let line = parse_data.peek_line();
+        let content = line.trim_start();
+        if content.strip_prefix(tag).is_some_and(is_heredoc_end) {
+            let indentation = &line[..line.len() - content.len()];
+            parse_data.skip_chars(indentation.chars().count() + tag.chars().count());
+            let value: Vec<&str> = lines.iter().map(|l: &String| l.strip_prefix(indentation).unwrap_or(l.trim_start())).collect();
+            string_elements.push(ISElem::Literal(value.join("\n")));
+            return Ok(());
+        }
+        parse_data.skip_chars(line.chars().count() + 1);
+        lines.push(line);
+    }
+    Err(Error::UnexpectedEOF)
+}
+
+/// Checks if the rest of the closing line of a heredoc after its tag may follow a string: nothing,
+/// a terminator, a comment or another part of the string.
+fn is_heredoc_end(rest: &str) -> bool {
+    let rest = rest.trim_start();
+    let is_raw_string = rest.strip_prefix('r').is_some_and(|r| r.trim_start_matches('#').starts_with('"'));
+    rest.is_empty() || rest.starts_with([';', ',', ']', '#', '|', '"']) || rest.starts_with("<<") || is_raw_string
+}
+
+/// Checks if a value that starts with the char is a string.
+pub(super) fn is_string_start(parse_data: &ParseData, c: char) -> bool {
+    matches!(c, '|' | '"' | 'r') || (c == '<' && parse_data.upcoming("<<"))
+}
+
 fn string_insert_variable(parse_data: &mut ParseData, string_elements: &mut Vec<ISElem>) -> Result<(), Error> {
     let mut state;
     let mut value = String::new();
@@ -131,36 +277,53 @@ fn string_insert_variable(parse_data: &mut ParseData, string_elements: &mut Vec<
                 state = VPS::Variable(s);
             }
             (VPS::Variable(KeyType::NoQuotes), '|') => {
-                string_elements.push(ISElem::Variable(link, value));
+                string_elements.push(ISElem::Variable(link, value, None));
                 return Ok(());
             }
             (VPS::Variable(KeyType::Quotes), '"') => {
-                string_elements.push(ISElem::Variable(link.clone(), value));
+                string_elements.push(ISElem::Variable(link.clone(), value, None));
                 state = VPS::BeforeComma;
                 value = String::new();
             }
             (VPS::Variable(KeyType::NoQuotes), ',') => {
-                string_elements.push(ISElem::Variable(link.clone(), value));
+                string_elements.push(ISElem::Variable(link.clone(), value, None));
                 state = VPS::AfterComma;
                 value = String::new();
             }
             (VPS::Variable(KeyType::NoQuotes), '#') => {
                 parse_data.skip_comment();
                 state = VPS::BeforeComma;
-                string_elements.push(ISElem::Variable(link.clone(), value));
+                string_elements.push(ISElem::Variable(link.clone(), value, None));
                 value = String::new();
             }
             (VPS::Variable(KeyType::NoQuotes), c) if c.is_whitespace() => {
                 state = VPS::BeforeComma;
-                string_elements.push(ISElem::Variable(link.clone(), value));
+                string_elements.push(ISElem::Variable(link.clone(), value, None));
+                value = String::new();
+            }
+            (VPS::Variable(KeyType::NoQuotes), ':') => {
+                string_elements.push(ISElem::Variable(link.clone(), value, None));
                 value = String::new();
+                match add_format_spec(parse_data, string_elements)? {
+                    '|' => return Ok(()),
+                    ',' => state = VPS::AfterComma,
+                    _ => state = VPS::BeforeComma
+                }
             }
             (VPS::Variable(_), '.') => value.push('.'),
+            (VPS::Variable(KeyType::NoQuotes), '[') => push_quoted_key(parse_data, &mut value)?,
             (VPS::Variable(_), c) if is_char_reserved(c) => return Err(illegal_char_err(c, parse_data)),
             (VPS::Variable(_), c) => value.push(c),
             (VPS::BeforeComma | VPS::AfterComma, '#') => parse_data.skip_comment(),
             (VPS::BeforeComma | VPS::AfterComma, c) if c.is_whitespace() => (),
             (VPS::BeforeComma, ',') => state = VPS::AfterComma,
+            (VPS::BeforeComma, ':') if matches!(string_elements.last(), Some(ISElem::Variable(_, _, None))) => {
+                match add_format_spec(parse_data, string_elements)? {
+                    '|' => return Ok(()),
+                    ',' => state = VPS::AfterComma,
+                    _ => ()
+                }
+            }
             (VPS::BeforeComma | VPS::AfterComma, '|') => return Ok(()),
             (VPS::BeforeComma, c) => return Err(illegal_char_err(c, parse_data)),
             (VPS::AfterComma, '"') => state = VPS::Start(KeyType::Quotes, 0),
@@ -175,6 +338,55 @@ fn string_insert_variable(parse_data: &mut ParseData, string_elements: &mut Vec<
     Err(Error::UnexpectedEOF)
 }
 
+/// Reads a key in quotes after the `[` of a variable, like `"example.com"]` in
+/// `|hosts["example.com"]|`, and adds it to the path of the variable.
+fn push_quoted_key(parse_data: &mut ParseData, path: &mut String) -> Result<(), Error> {
+    match parse_data.next_char() {
+        Some('"') => (),
+        Some(c) => return Err(illegal_char_err(c, parse_data)),
+        None => return Err(Error::UnexpectedEOF)
+    }
+    let mut key = String::new();
+    while let Some(c) = parse_data.next_char() {
+        if c != '"' {
+            key.push(c);
+            continue;
+        }
+        return match parse_data.next_char() {
+            Some(']') => {
+                path.push_str(&format!("[\"{key}\"]"));
+                Ok(())
+            }
+            Some(c) => Err(illegal_char_err(c, parse_data)),
+            None => Err(Error::UnexpectedEOF)
+        };
+    }
+    Err(Error::UnexpectedEOF)
+}
+
+/// Reads the format spec after the `:` of a variable, like `05` in `|port:05|`, and adds it to
+/// the last variable. Returns the char that ended the spec.
+fn add_format_spec(parse_data: &mut ParseData, string_elements: &mut [ISElem]) -> Result<char, Error> {
+    let line = parse_data.line;
+    let mut spec = String::new();
+    while let Some(c) = parse_data.next_char() {
+        match c {
+            c if !spec.is_empty() && (matches!(c, '|' | ',' | '#') || c.is_whitespace()) => {
+                if c == '#' {
+                    parse_data.skip_comment();
+                }
+                if let Some(ISElem::Variable(_, _, format)) = string_elements.last_mut() {
+                    *format = Some(FormatSpec{spec, line});
+                }
+                return Ok(c);
+            }
+            c if c.is_whitespace() || (is_char_reserved(c) && c != '.') => return Err(illegal_char_err(c, parse_data)),
+            c => spec.push(c)
+        }
+    }
+    Err(Error::UnexpectedEOF)
+}
+
 pub(super) fn bool(parse_data: &mut ParseData, terminator_type: TerminatorType) -> Result<bool, Error> {
     let terminators = match terminator_type {
         TerminatorType::Struct => vec![';'],
@@ -205,6 +417,133 @@ pub(super) fn bool(parse_data: &mut ParseData, terminator_type: TerminatorType)
     Err(Error::UnexpectedEOF)
 }
 
+pub(super) fn null(parse_data: &mut ParseData, terminator_type: TerminatorType) -> StdResult {
+    let terminators = match terminator_type {
+        TerminatorType::Struct => vec![';'],
+        TerminatorType::Array => vec![',', ']']
+    };
+    let mut value = String::new();
+    while let Some(c) = parse_data.next_char() {
+        match (value.as_str(), c) {
+            #[allow(clippy::unnested_or_patterns)]
+            ("", 'n') |
+            ("n", 'u') |
+            ("nu", 'l') |
+            ("nul", 'l') => value.push(c),
+            ("null", '#') => parse_data.skip_comment(),
+            ("null", c) if c.is_whitespace() => (),
+            ("null", c) if terminators.contains(&c) => return Ok(Element::PmlNull),
+            (_, c) => return Err(illegal_char_err(c, parse_data))
+        }
+    }
+    Err(Error::UnexpectedEOF)
+}
+
+/// Parses a value that is a word without quotes, like `true`, `false`, `null`, `inf` or `nan`.
+pub(super) fn word(parse_data: &mut ParseData, terminator_type: TerminatorType) -> StdResult {
+    let value = token(parse_data, terminator_type)?;
+    word_literal(value, parse_data.line)
+}
+
+fn word_literal(value: String, line: u32) -> StdResult {
+    match value.as_str() {
+        "true" => Ok(true.into()),
+        "false" => Ok(false.into()),
+        "null" => Ok(Element::PmlNull),
+        _ => special_float(&value).map(Element::PmlF32).ok_or(Error::UnknownValue {
+            line,
+            value
+        })
+    }
+}
+
+/// Returns the float for `inf`, `-inf`, `+inf`, `nan`, `-nan` or `+nan`. Like other inferred
+/// floats, they are `f32` values; `<f64> nan` is needed for an `f64`.
+fn special_float(value: &str) -> Option<f32> {
+    match value {
+        "inf" | "+inf" => Some(f32::INFINITY),
+        "-inf" => Some(f32::NEG_INFINITY),
+        "nan" | "+nan" => Some(f32::NAN),
+        "-nan" => Some(-f32::NAN),
+        _ => None
+    }
+}
+
+/// Parses a single character in quotes, like `"a"` or `"\n"`.
+pub(super) fn char(parse_data: &mut ParseData, terminator_type: TerminatorType) -> Result<char, Error> {
+    let terminators = match terminator_type {
+        TerminatorType::Struct => vec![';'],
+        TerminatorType::Array => vec![',', ']']
+    };
+    match parse_data.next_non_whitespace() {
+        Some('"') => (),
+        Some(c) => return Err(illegal_char_err(c, parse_data)),
+        None => return Err(Error::UnexpectedEOF)
+    }
+    let value = match parse_data.next_char() {
+        Some('\\') => super::next_escape_sequence(parse_data)?,
+        Some('"') => return Err(illegal_char_err('"', parse_data)),
+        Some(c) => c,
+        None => return Err(Error::UnexpectedEOF)
+    };
+    match parse_data.next_char() {
+        Some('"') => (),
+        Some(c) => return Err(illegal_char_err(c, parse_data)),
+        None => return Err(Error::UnexpectedEOF)
+    }
+    parse_data.try_skip_comment();
+    match parse_data.next_non_whitespace() {
+        Some(c) if terminators.contains(&c) => Ok(value),
+        Some(c) => Err(illegal_char_err(c, parse_data)),
+        None => Err(Error::UnexpectedEOF)
+    }
+}
+
+/// Parses binary data in hex or base64, like `hex"00ff"` or `base64"AP8="`. Whitespace between
+/// the quotes is ignored, so long values can span multiple lines.
+pub(super) fn bytes(parse_data: &mut ParseData, terminator_type: TerminatorType) -> Result<Vec<u8>, Error> {
+    let terminators = match terminator_type {
+        TerminatorType::Struct => vec![';'],
+        TerminatorType::Array => vec![',', ']']
+    };
+    let mut encoding = String::new();
+    loop {
+        match parse_data.next_char() {
+            Some('"') => break,
+            Some(c) if c.is_ascii_alphanumeric() => encoding.push(c),
+            Some(c) if c.is_whitespace() && encoding.is_empty() => (),
+            Some(c) => return Err(illegal_char_err(c, parse_data)),
+            None => return Err(Error::UnexpectedEOF)
+        }
+    }
+    let mut value = String::new();
+    loop {
+        match parse_data.next_char() {
+            Some('"') => break,
+            Some(c) if c.is_whitespace() => (),
+            Some(c) => value.push(c),
+            None => return Err(Error::UnexpectedEOF)
+        }
+    }
+    let bytes = match encoding.as_str() {
+        "hex" => encoding::decode_hex(&value),
+        "base64" => encoding::decode_base64(&value),
+        _ => None
+    };
+    let Some(bytes) = bytes else {
+        return Err(Error::InvalidBytes {
+            line: parse_data.line,
+            value: format!("{encoding}\"{value}\"")
+        });
+    };
+    parse_data.try_skip_comment();
+    match parse_data.next_non_whitespace() {
+        Some(c) if terminators.contains(&c) => Ok(bytes),
+        Some(c) => Err(illegal_char_err(c, parse_data)),
+        None => Err(Error::UnexpectedEOF)
+    }
+}
+
 pub(super) fn pml_struct(parse_data: &mut ParseData, terminator_type: TerminatorType) -> Result<Rc<RefCell<WIPStruct>>, Error> {
     let terminators = match terminator_type {
         TerminatorType::Struct => vec![';'],
@@ -232,10 +571,80 @@ pub(super) fn pml_struct(parse_data: &mut ParseData, terminator_type: Terminator
     Err(Error::UnexpectedEOF)
 }
 
+pub(super) fn array(parse_data: &mut ParseData, key: &str) -> WIPResult {
+    parse_data.next_char();
+    parse_data.add_nested_name(key.to_string());
+    let res = arrays::untyped(parse_data)?;
+    parse_data.drop_last_nested_name();
+    match parse_data.next_non_whitespace() {
+        Some(';') => Ok(res),
+        Some(c) => Err(illegal_char_err(c, parse_data)),
+        None => Err(Error::UnexpectedEOF)
+    }
+}
+
+fn array_item(parse_data: &mut ParseData, index: usize) -> Result<WIPArrayItem, Error> {
+    let item = match parse_data.next_non_whitespace_peek() {
+        Some('|' | '!' | '(') => expression_or_string(parse_data, TerminatorType::Array)?.into(),
+        Some(c) if c.is_ascii_alphabetic() && is_function_call(parse_data) => expression_or_string(parse_data, TerminatorType::Array)?.into(),
+        Some(_) if is_expression(parse_data) => expression_or_string(parse_data, TerminatorType::Array)?.into(),
+        Some(c) if is_string_start(parse_data, c) => WIPArrayItem::String(string(parse_data, TerminatorType::Array)?),
+        Some('t' | 'f' | 'n' | 'i') => WIPArrayItem::Element(word(parse_data, TerminatorType::Array)?),
+        Some('{') => WIPArrayItem::Struct(pml_struct(parse_data, TerminatorType::Array)?),
+        Some('<') => forced(parse_data, TerminatorType::Array, &index.to_string())?.into(),
+        Some('[') => {
+            parse_data.next_char();
+            let item = arrays::untyped(parse_data)?.into();
+            parse_data.try_skip_comment();
+            match parse_data.next_non_whitespace() {
+                Some(',' | ']') => item,
+                Some(c) => return Err(illegal_char_err(c, parse_data)),
+                None => return Err(Error::UnexpectedEOF)
+            }
+        }
+        Some('.') if parse_data.upcoming("..") => spread(parse_data)?,
+        Some('.' | '-' | '+') => WIPArrayItem::Element(number(parse_data, TerminatorType::Array)?),
+        Some(c) if c.is_ascii_digit() => WIPArrayItem::Element(number(parse_data, TerminatorType::Array)?),
+        Some(c) => return Err(illegal_char_err(c, parse_data)),
+        None => return Err(Error::UnexpectedEOF)
+    };
+    Ok(item)
+}
+
 pub(super) fn number(parse_data: &mut ParseData, terminator_type: TerminatorType) -> StdResult {
-    let (num_type, value) = get_number_type_and_string(parse_data, terminator_type)?;
+    let value = token(parse_data, terminator_type)?;
+    number_literal(value, parse_data.line)
+}
+
+/// Parses a number, date, time or amount with a unit, whose type is inferred from the value.
+fn number_literal(value: String, line: u32) -> StdResult {
+    if datetime::is_literal(&value) {
+        return datetime::parse_literal(&value).ok_or(Error::InvalidDateTime {
+            line,
+            value
+        });
+    }
+    if let Some(float) = special_float(&value) {
+        return Ok(float.into());
+    }
+    if !is_number_literal(&value) && value.contains(|c: char| c.is_ascii_alphabetic()) {
+        if let Some(duration) = units::parse_duration(&value) {
+            return Ok(Element::PmlDuration(duration));
+        }
+        return units::parse_byte_size(&value).map(Element::PmlByteSize).ok_or(Error::InvalidUnit {
+            line,
+            value
+        });
+    }
+    let (num_type, normalized) = number_type_and_string(value.clone(), line)?;
+    #[cfg(feature = "bignum")]
+    if !matches!(num_type, NumType::Decimal) && normalized.parse::<i128>().is_err() && normalized.parse::<u128>().is_err() {
+        if let Ok(num) = normalized.parse::<BigInt>() {
+            return Ok(num.into());
+        }
+    }
     match num_type {
-        NumType::Signed => match value.parse::<i128>() {
+        NumType::Signed => match normalized.parse::<i128>() {
             Ok(num128) => {
                 match i8::try_from(num128) {
                     Ok(num8) => Ok(num8.into()),
@@ -252,12 +661,12 @@ pub(super) fn number(parse_data: &mut ParseData, terminator_type: TerminatorType
                 }
             }
             Err(e) => Err(Error::ParseNumberError{
-                line: parse_data.line,
+                line,
                 value,
                 error: e.into()
             })
         }
-        NumType::Unsigned => match value.parse::<u128>() {
+        NumType::Unsigned => match normalized.parse::<u128>() {
             Ok(num128) => {
                 match u8::try_from(num128) {
                     Ok(num8) => Ok(num8.into()),
@@ -274,18 +683,18 @@ pub(super) fn number(parse_data: &mut ParseData, terminator_type: TerminatorType
                 }
             }
             Err(e) => Err(Error::ParseNumberError{
-                line: parse_data.line,
+                line,
                 value,
                 error: e.into()
             })
         }
-        NumType::Decimal => match value.parse::<f64>() {
-            Ok(num64) => match value.parse::<f32>() {
+        NumType::Decimal => match normalized.parse::<f64>() {
+            Ok(num64) => match normalized.parse::<f32>() {
                 Ok(num32) => Ok(num32.into()),
                 Err(_) => Ok(num64.into())
             }
             Err(e) => Err(Error::ParseNumberError{
-                line: parse_data.line,
+                line,
                 value,
                 error: e.into()
             })
@@ -293,45 +702,90 @@ pub(super) fn number(parse_data: &mut ParseData, terminator_type: TerminatorType
     }
 }
 
-fn get_number_type_and_string(parse_data: &mut ParseData, terminator_type: TerminatorType) -> Result<(NumType, String), Error> {
+/// Reads a number. Returns the number as it was written, for errors, and in a form that Rust can
+/// parse.
+fn get_number_string(parse_data: &mut ParseData, terminator_type: TerminatorType) -> Result<(String, String), Error> {
+    let value = token(parse_data, terminator_type)?;
+    let (_, normalized) = number_type_and_string(value.clone(), parse_data.line)?;
+    Ok((value, normalized))
+}
+
+fn number_type_and_string(value: String, line: u32) -> Result<(NumType, String), Error> {
+    let normalized = match normalize_number(&value) {
+        Ok(normalized) => normalized,
+        Err(error) => return Err(Error::ParseNumberError{line, value, error})
+    };
+    let num_type = if normalized.contains(['.', 'e', 'E']) {
+        NumType::Decimal
+    }
+    else if normalized.starts_with('-') {
+        NumType::Signed
+    }
+    else {
+        NumType::Unsigned
+    };
+    Ok((num_type, normalized))
+}
+
+/// Checks if a value without quotes is a number, like `-1.5e3` or `0xFF`, and not a value with a
+/// unit.
+fn is_number_literal(value: &str) -> bool {
+    let unsigned = value.strip_prefix(['-', '+']).unwrap_or(value);
+    ["0x", "0o", "0b"].iter().any(|p| unsigned.starts_with(p))
+        || unsigned.chars().all(|c| c.is_ascii_digit() || ['_', '.', 'e', 'E', '-', '+'].contains(&c))
+}
+
+/// Brings a number into a form that Rust can parse: digit separators and a leading `+` are
+/// removed, and numbers with a prefix like `0x`, `0o` or `0b` are converted to decimal. Invalid
+/// numbers are returned unchanged, so parsing them fails later on.
+fn normalize_number(value: &str) -> Result<String, ParseNumberError> {
+    let (sign, unsigned) = match value.get(..1) {
+        Some("-") => ("-", &value[1..]),
+        Some("+") => ("", &value[1..]),
+        _ => ("", value)
+    };
+    let separators_valid = unsigned.char_indices().all(|(i, c)| {
+        c != '_' || (i > 0 && unsigned[..i].ends_with(|c: char| c.is_ascii_alphanumeric()) && unsigned[i + 1..].starts_with(|c: char| c.is_ascii_alphanumeric()))
+    });
+    if !separators_valid || unsigned.starts_with(['-', '+']) {
+        return Ok(value.to_string());
+    }
+    let digits = unsigned.replace('_', "");
+    let radix = match digits.get(..2) {
+        Some("0x") => 16,
+        Some("0o") => 8,
+        Some("0b") => 2,
+        _ => return Ok(format!("{sign}{digits}"))
+    };
+    if !digits[2..].chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Ok(value.to_string());
+    }
+    Ok(format!("{sign}{}", u128::from_str_radix(&digits[2..], radix)?))
+}
+
+/// Reads a value without quotes, like a number or a date, and the terminator after it.
+fn token(parse_data: &mut ParseData, terminator_type: TerminatorType) -> Result<String, Error> {
     let terminators = match terminator_type {
         TerminatorType::Struct => vec![';'],
         TerminatorType::Array => vec![',', ']']
     };
     let mut value = String::new();
-    let mut allow_negative_sign = true;
-    let mut allow_decimal_point = true;
-    let mut num_type = NumType::Unsigned;
     while let Some(c) = parse_data.next_char() {
         match c {
-            '-' if allow_negative_sign => {
-                allow_negative_sign = false;
-                num_type = NumType::Signed;
-                value.push('-');
-            }
-            '.' if allow_decimal_point => {
-                allow_decimal_point = false;
-                allow_negative_sign = false;
-                num_type = NumType::Decimal;
-                value.push('.');
-            }
-            '#' => {
-                parse_data.skip_comment();
-                return match parse_data.next_non_whitespace() {
-                    Some(c) if terminators.contains(&c) => Ok((num_type, value)),
-                    Some(c) => Err(illegal_char_err(c, parse_data)),
-                    None => Err(Error::UnexpectedEOF)
-                }
+            '#' => parse_data.skip_comment(),
+            c if c.is_whitespace() => parse_data.try_skip_comment(),
+            c if terminators.contains(&c) => return Ok(value),
+            c if c.is_ascii_alphanumeric() || ['-', '+', '.', ':', '_'].contains(&c) => {
+                value.push(c);
+                continue;
             }
-            c if c.is_ascii_digit() => value.push(c),
-            c if c.is_whitespace() => return match parse_data.next_non_whitespace() {
-                Some(c) if terminators.contains(&c) => Ok((num_type, value)),
-                Some(c) => Err(illegal_char_err(c, parse_data)),
-                None => Err(Error::UnexpectedEOF)
-            },
-            c if terminators.contains(&c) => return Ok((num_type, value)),
             c => return Err(illegal_char_err(c, parse_data))
         }
+        return match parse_data.next_non_whitespace() {
+            Some(c) if terminators.contains(&c) => Ok(value),
+            Some(c) => Err(illegal_char_err(c, parse_data)),
+            None => Err(Error::UnexpectedEOF)
+        };
     }
     Err(Error::UnexpectedEOF)
 }
@@ -339,84 +793,251 @@ fn get_number_type_and_string(parse_data: &mut ParseData, terminator_type: Termi
 pub(super) fn forced(parse_data: &mut ParseData, terminator_type: TerminatorType, key: &str) -> WIPResult {
     parse_data.next_char();
     let mut ftype_string = String::new();
+    let mut nullable = false;
+    let mut map_type = None;
     while let Some(c) = parse_data.next_char() {
         match c {
+            '<' if ftype_string.trim() == "map" && map_type.is_none() && !nullable => map_type = Some(map_value_type(parse_data, key)?),
+            '?' if !nullable => nullable = true,
             '>' => {
                 parse_data.try_skip_comment();
-                let force_type = match ftype_string.trim() {
-                    "i8" => I8,
-                    "i16" => I16,
-                    "i32" => I32,
-                    "i64" => I64,
-                    "i128" => I128,
-                    "u8" => U8,
-                    "u16" => U16,
-                    "u32" => U32,
-                    "u64" => U64,
-                    "u128" => U128,
-                    "f32" => F32,
-                    "f64" => F64,
-                    "b" => Bool,
-                    "struct" => Struct,
-                    "str" => FString,
-                    t => return Err(Error::UnknownForcedType {
-                        key: parse_data.get_full_struct_path() + "." + key,
-                        type_name: t.to_string()
-                    })
-                };
-                if parse_data.next_non_whitespace_peek() == Some('[') {
-                    parse_data.next_char();
-                    parse_data.add_nested_name(key.to_string());
-                    let res = match force_type {
-                        I8 => arrays::i8(parse_data)?,
-                        I16 => arrays::i16(parse_data)?,
-                        I32 => arrays::i32(parse_data)?,
-                        I64 => arrays::i64(parse_data)?,
-                        I128 => arrays::i128(parse_data)?,
-                        U8 => arrays::u8(parse_data)?,
-                        U16 => arrays::u16(parse_data)?,
-                        U32 => arrays::u32(parse_data)?,
-                        U64 => arrays::u64(parse_data)?,
-                        U128 => arrays::u128(parse_data)?,
-                        F32 => arrays::f32(parse_data)?,
-                        F64 => arrays::f64(parse_data)?,
-                        Bool => arrays::bool(parse_data)?,
-                        Struct => arrays::structs(parse_data)?,
-                        FString => arrays::strings(parse_data)?,
-                    };
-                    parse_data.drop_last_nested_name();
-                    match parse_data.next_non_whitespace() {
-                        Some(';') => return Ok(res),
-                        Some(c) => return Err(illegal_char_err(c, parse_data)),
-                        None => return Err(Error::UnexpectedEOF)
+                if let Some((value_type, nullable_values)) = map_type {
+                    if nullable && parse_data.next_non_whitespace_peek() == Some('n') {
+                        return Ok(null(parse_data, terminator_type)?.into());
                     }
+                    return map(parse_data, terminator_type, value_type, nullable_values);
                 }
-                if force_type == Bool {
-                    return Ok(bool(parse_data, TerminatorType::Struct)?.into());
-                }
-                if force_type == FString {
-                    return Ok(string(parse_data, TerminatorType::Struct)?.into());
-                }
-                if force_type == Struct {
-                    return Ok(pml_struct(parse_data, TerminatorType::Struct)?.into());
-                }
-                let (_, value) = get_number_type_and_string(parse_data, terminator_type)?;
-                return match parse_forced_number(&value, force_type) {
-                    Ok(element) => Ok(element.into()),
-                    Err(error) => Err(Error::ParseNumberError {
-                        error,
-                        value,
-                        line: parse_data.line,
-                    })
-                };
+                let force_type = force_category(parse_data, ftype_string.trim(), key)?;
+                return forced_value(parse_data, terminator_type, key, force_type, nullable);
             }
-            c if is_char_reserved(c) => return Err(illegal_char_err(c, parse_data)),
+            c if is_char_reserved(c) || (nullable && !c.is_whitespace()) || (map_type.is_some() && !c.is_whitespace()) => return Err(illegal_char_err(c, parse_data)),
             c => ftype_string.push(c)
         }
     }
     Err(Error::UnexpectedEOF)
 }
 
+/// Returns the forced type with the provided name, which is either a built-in type or a declared
+/// enum.
+fn force_category(parse_data: &ParseData, type_name: &str, key: &str) -> Result<ForceCategory, Error> {
+    if let Some(force_type) = built_in_force_category(type_name) {
+        return Ok(force_type);
+    }
+    match parse_data.meta_info.enum_index(type_name) {
+        Some(index) => Ok(FEnum(index)),
+        None => Err(Error::UnknownForcedType {
+            key: parse_data.get_full_struct_path() + "." + key,
+            type_name: type_name.to_string()
+        })
+    }
+}
+
+fn built_in_force_category(type_name: &str) -> Option<ForceCategory> {
+    Some(match type_name {
+        "i8" => I8,
+        "i16" => I16,
+        "i32" => I32,
+        "i64" => I64,
+        "i128" => I128,
+        "u8" => U8,
+        "u16" => U16,
+        "u32" => U32,
+        "u64" => U64,
+        "u128" => U128,
+        "f32" => F32,
+        "f64" => F64,
+        "b" => Bool,
+        "struct" => Struct,
+        "str" => FString,
+        "char" => FChar,
+        "bytes" => FBytes,
+        #[cfg(feature = "bignum")]
+        "bigint" => FBigInt,
+        #[cfg(feature = "bignum")]
+        "decimal" => FDecimal,
+        _ => return None
+    })
+}
+
+/// Returns the name of the forced type, which is also the type name of its values.
+fn force_type_name(parse_data: &ParseData, force_type: ForceCategory) -> String {
+    let name = match force_type {
+        I8 => "i8",
+        I16 => "i16",
+        I32 => "i32",
+        I64 => "i64",
+        I128 => "i128",
+        U8 => "u8",
+        U16 => "u16",
+        U32 => "u32",
+        U64 => "u64",
+        U128 => "u128",
+        F32 => "f32",
+        F64 => "f64",
+        Bool => "b",
+        Struct => "struct",
+        FString => "str",
+        FChar => "char",
+        FBytes => "bytes",
+        FEnum(index) => parse_data.meta_info.enum_at(index).0,
+        #[cfg(feature = "bignum")]
+        FBigInt => "bigint",
+        #[cfg(feature = "bignum")]
+        FDecimal => "decimal",
+    };
+    name.to_string()
+}
+
+/// Parses a value with a forced type, after the type. The value may be a typed array.
+fn forced_value(parse_data: &mut ParseData, terminator_type: TerminatorType, key: &str, force_type: ForceCategory, nullable: bool) -> WIPResult {
+    if parse_data.next_non_whitespace_peek() == Some('[') {
+        if nullable {
+            return Err(Error::NullableArray {
+                key: parse_data.get_full_struct_path() + "." + key,
+                type_name: force_type_name(parse_data, force_type)
+            });
+        }
+        parse_data.next_char();
+        parse_data.add_nested_name(key.to_string());
+        let terminators = match terminator_type {
+            TerminatorType::Struct => vec![';'],
+            TerminatorType::Array => vec![',', ']']
+        };
+        let res = arrays::typed(parse_data, force_type)?;
+        parse_data.drop_last_nested_name();
+        parse_data.try_skip_comment();
+        return match parse_data.next_non_whitespace() {
+            Some(c) if terminators.contains(&c) => Ok(res),
+            Some(c) => Err(illegal_char_err(c, parse_data)),
+            None => Err(Error::UnexpectedEOF)
+        };
+    }
+    if nullable && [Bool, FString, Struct, FChar, FBytes].contains(&force_type) && parse_data.next_non_whitespace_peek() == Some('n') {
+        return Ok(null(parse_data, terminator_type)?.into());
+    }
+    if force_type == Bool {
+        return Ok(bool(parse_data, terminator_type)?.into());
+    }
+    if force_type == FString {
+        return Ok(string(parse_data, terminator_type)?.into());
+    }
+    if force_type == Struct {
+        return Ok(pml_struct(parse_data, terminator_type)?.into());
+    }
+    if force_type == FChar {
+        return Ok(char(parse_data, terminator_type)?.into());
+    }
+    if force_type == FBytes {
+        return Ok(Element::PmlBytes(bytes(parse_data, terminator_type)?).into());
+    }
+    let value = token(parse_data, terminator_type)?;
+    if nullable && value == "null" {
+        return Ok(Element::PmlNull.into());
+    }
+    if let FEnum(index) = force_type {
+        return Ok(enum_variant(parse_data, index, value)?.into());
+    }
+    Ok(forced_number(value, force_type, parse_data.line)?.into())
+}
+
+/// Parses a number with a forced type that is a number type, like `0xFF` for a `u8`.
+fn forced_number(value: String, force_type: ForceCategory, line: u32) -> StdResult {
+    let (_, normalized) = number_type_and_string(value.clone(), line)?;
+    parse_forced_number(&normalized, force_type).map_err(|error| Error::ParseNumberError {
+        error,
+        value,
+        line
+    })
+}
+
+/// Reads the forced type of a number in an expression, like `<u8>` in `|w| + <u8> 100`, after
+/// its opening `<`. Only number types are allowed.
+fn number_force_category(parse_data: &mut ParseData) -> Result<ForceCategory, Error> {
+    let mut type_name = String::new();
+    while let Some(c) = parse_data.next_char() {
+        match c {
+            '>' => return match built_in_force_category(type_name.trim()) {
+                Some(Bool | Struct | FString | FChar | FBytes) => match parse_data.next_non_whitespace() {
+                    Some(c) => Err(illegal_char_err(c, parse_data)),
+                    None => Err(Error::UnexpectedEOF)
+                },
+                Some(force_type) => Ok(force_type),
+                None => Err(Error::UnknownForcedType {
+                    key: parse_data.get_full_struct_path(),
+                    type_name: type_name.trim().to_string()
+                })
+            },
+            c if is_char_reserved(c) => return Err(illegal_char_err(c, parse_data)),
+            c => type_name.push(c)
+        }
+    }
+    Err(Error::UnexpectedEOF)
+}
+
+/// Reads the type of the values of a map, like `u16` in `<map<u16>>`, after its opening `<`.
+/// Returns the type and whether the values may be null.
+fn map_value_type(parse_data: &mut ParseData, key: &str) -> Result<(ForceCategory, bool), Error> {
+    let mut type_name = String::new();
+    let mut nullable = false;
+    while let Some(c) = parse_data.next_char() {
+        match c {
+            '?' if !nullable => nullable = true,
+            '>' => return Ok((force_category(parse_data, type_name.trim(), key)?, nullable)),
+            c if is_char_reserved(c) || (nullable && !c.is_whitespace()) => return Err(illegal_char_err(c, parse_data)),
+            c => type_name.push(c)
+        }
+    }
+    Err(Error::UnexpectedEOF)
+}
+
+/// Parses a map after its forced type, like `{ "example.com" = 443; }`. Keys in quotes may contain
+/// any characters, and all values have the type of the map.
+fn map(parse_data: &mut ParseData, terminator_type: TerminatorType, value_type: ForceCategory, nullable: bool) -> WIPResult {
+    let terminators = match terminator_type {
+        TerminatorType::Struct => vec![';'],
+        TerminatorType::Array => vec![',', ']']
+    };
+    match parse_data.next_non_whitespace() {
+        Some('{') => (),
+        Some(c) => return Err(illegal_char_err(c, parse_data)),
+        None => return Err(Error::UnexpectedEOF)
+    }
+    let temp_map = Rc::new(RefCell::new(WIPStruct::init_map()));
+    parse_data.add_nested_ref(temp_map.clone());
+    parse_data.try_skip_comment();
+    while let Some(c) = parse_data.next_non_whitespace_peek() {
+        if c == '}' {
+            parse_data.next_char();
+            parse_data.drop_last_nested_ref();
+            parse_data.try_skip_comment();
+            return match parse_data.next_non_whitespace() {
+                Some(c) if terminators.contains(&c) => Ok(temp_map.into()),
+                Some(c) => Err(illegal_char_err(c, parse_data)),
+                None => Err(Error::UnexpectedEOF)
+            };
+        }
+        let key = super::get_map_key(parse_data)?;
+        parse_data.try_skip_comment();
+        let value = forced_value(parse_data, TerminatorType::Struct, &key, value_type, nullable)?;
+        temp_map.borrow_mut().add(key, value)?;
+        parse_data.try_skip_comment();
+    }
+    Err(Error::UnexpectedEOF)
+}
+
+/// Returns the value of the enum at the index, if it has a variant with that name.
+fn enum_variant(parse_data: &ParseData, index: usize, variant: String) -> StdResult {
+    let (name, variants) = parse_data.meta_info.enum_at(index);
+    if !variants.contains(&variant) {
+        return Err(Error::UnknownVariant {
+            line: parse_data.line,
+            name: name.to_string(),
+            variant
+        });
+    }
+    Ok(Element::PmlEnum(EnumValue::new(name, &variant)))
+}
+
 fn parse_forced_number(value: &str, force_type: ForceCategory) -> Result<Element, ParseNumberError> {
     Ok(match force_type {
         F32 => value.parse::<f32>()?.into(),
@@ -431,7 +1052,11 @@ fn parse_forced_number(value: &str, force_type: ForceCategory) -> Result<Element
         U32 => value.parse::<u32>()?.into(),
         U64 => value.parse::<u64>()?.into(),
         U128 => value.parse::<u128>()?.into(),
-        Bool|Struct|FString => unreachable!("This should have been caught before the function call.")
+        #[cfg(feature = "bignum")]
+        FBigInt => value.parse::<BigInt>()?.into(),
+        #[cfg(feature = "bignum")]
+        FDecimal => value.parse::<Decimal>()?.into(),
+        Bool|Struct|FString|FChar|FBytes|FEnum(_) => unreachable!("This should have been caught before the function call.")
     })
 }
 
@@ -1,44 +1,102 @@
 //! Functions for parsing stuff to [`PmlStructs`](crate::PmlStruct).
-use std::{fs, rc::Rc, cell::RefCell};
-use crate::{PmlStruct, errors::ParseError as Error};
+use std::{fs, iter, path::Path, rc::Rc, cell::RefCell};
+use crate::{PmlStruct, Element, errors::ParseError as Error, functions::Functions};
 
 mod types;
 mod get_value;
-pub(crate) use types::{ISElem, KeyType, ParseData, WIPElement, WIPStruct, MetaInfo};
+mod expression;
+pub(crate) use expression::{Expression, Expr, Call, Operator, convert_number, integer, is_less};
+pub(crate) use types::{ISElem, FormatSpec, KeyType, ParseData, WIPElement, WIPArrayItem, WIPStruct, MetaInfo, Extension, ForcedArrayType};
 use types::TerminatorType;
 
 /// Parses a file to a [`PmlStruct`](crate::PmlStruct).
 ///
 /// Takes the path to a file, parses it, and returns a `PmlStruct` if the file could be parsed
-/// successfully, or an error if one occured.
+/// successfully, or an error if one occured. Values may call the
+/// [built-in functions](crate::functions) of [`Functions::new`], which can not read environment
+/// variables or files. Use [`file_with_functions`] to call other functions.
 ///
 /// # Errors
 /// This function returns a [`ParseError`](crate::errors::ParseError) if the file could not be
 /// opened, or if it contains invalid syntax or data.
 pub fn file(file: &str) -> Result<PmlStruct, Error> {
+    file_with_functions(file, &Functions::new())
+}
+
+/// Parses a file to a [`PmlStruct`](crate::PmlStruct), with a custom set of
+/// [functions](crate::functions) that can be called in its values.
+///
+/// # Errors
+/// This function returns a [`ParseError`](crate::errors::ParseError) if the file could not be
+/// opened, or if it contains invalid syntax or data.
+pub fn file_with_functions(file: &str, functions: &Functions) -> Result<PmlStruct, Error> {
     let file_content = fs::read_to_string(file)?;
-    parse_pml_string(&file_content)
+    let dir = Path::new(file).parent().unwrap_or(Path::new(""));
+    string_with_functions(&file_content, &functions.in_dir(dir))
 }
 
-fn parse_pml_string(input: &str) -> Result<PmlStruct, Error> {
-    let mut parse_data = ParseData::init(input);
+/// Parses a string to a [`PmlStruct`](crate::PmlStruct).
+///
+/// Takes the content of a PML file, parses it, and returns a `PmlStruct` if it could be parsed
+/// successfully, or an error if one occured. Values may call the
+/// [built-in functions](crate::functions) of [`Functions::new`], which can not read environment
+/// variables or files. Use [`string_with_functions`] to call other functions.
+///
+/// # Errors
+/// This function returns a [`ParseError`](crate::errors::ParseError) if the input contains invalid
+/// syntax or data.
+pub fn string(input: &str) -> Result<PmlStruct, Error> {
+    string_with_functions(input, &Functions::new())
+}
+
+/// Parses a string to a [`PmlStruct`](crate::PmlStruct), with a custom set of
+/// [functions](crate::functions) that can be called in its values.
+///
+/// # Errors
+/// This function returns a [`ParseError`](crate::errors::ParseError) if the input contains invalid
+/// syntax or data.
+pub fn string_with_functions(input: &str, functions: &Functions) -> Result<PmlStruct, Error> {
+    let mut parse_data = ParseData::init(input, functions);
     let temp_struct = Rc::new(RefCell::new(WIPStruct::init()));
     parse_data.add_nested_ref(temp_struct.clone());
     
-    get_meta_info(&mut parse_data)?;
+    parse_data.meta_info = get_meta_info(&mut parse_data)?;
     while parse_data.has_next_non_whitespace() {
         let (key, value) = get_key_value_pair(&mut parse_data)?;
         temp_struct.borrow_mut().add(key, value)?;
         parse_data.try_skip_comment();
     }
+    resolve(&temp_struct)
+}
+
+/// Parses a single value like `8080` or `"example.org"`, e.g. of a command-line override. The
+/// input must not contain anything but the value. It can not call any functions.
+pub(crate) fn value(input: &str) -> Result<Element, Error> {
+    let input = format!("{input};");
+    let functions = Functions::empty();
+    let mut parse_data = ParseData::init(&input, &functions);
+    let temp_struct = Rc::new(RefCell::new(WIPStruct::init()));
+    parse_data.add_nested_ref(temp_struct.clone());
+    let key = String::from("value");
+    let value = get_value_for_key(&mut parse_data, &key)?;
+    parse_data.try_skip_comment();
+    if let Some(c) = parse_data.next_non_whitespace() {
+        return Err(illegal_char_err(c, &parse_data));
+    }
+    temp_struct.borrow_mut().add(key.clone(), value)?;
+    resolve(&temp_struct)?.elements.remove(&key).ok_or(Error::UnexpectedEOF)
+}
+
+/// Resolves all values of the root struct once the whole input is parsed.
+fn resolve(temp_struct: &Rc<RefCell<WIPStruct>>) -> Result<PmlStruct, Error> {
     loop {
-        let (no_change, done) = temp_struct.borrow_mut().resolve_inc_strings();
-        let (no_change2, done2) = temp_struct.borrow().resolve_inc_strings_recursive();
+        let (no_change, done) = temp_struct.borrow_mut().resolve_inc_strings()?;
+        let (no_change2, done2) = temp_struct.borrow().resolve_inc_strings_recursive()?;
         if done && done2 {
             break;
         }
         if no_change && no_change2{
-            return Err(Error::IllegalDependency)
+            return Err(WIPStruct::extension_error(temp_struct).unwrap_or(Error::IllegalDependency))
         }
     }
     let struct_arrays = temp_struct.borrow().resolve_struct_arrays()?;
@@ -57,7 +115,7 @@ fn illegal_char_err(c: char, pd: &ParseData) -> Error {
     }
 }
 
-fn is_char_reserved(c: char) -> bool {
+pub(crate) fn is_char_reserved(c: char) -> bool {
     ['=', ';', ',', '<', '>', '{', '}', '(', ')', '"', '[', ']', ':', '|', '.', '+', '$', '!', '?', '#'].into_iter().any(|r| r == c)
 }
 
@@ -68,28 +126,46 @@ fn get_key_value_pair(parse_data: &mut ParseData) -> Result<(String, WIPElement)
         Some(_) => get_unquoted_key(parse_data),
         None => unreachable!(),
     }?;
+    let value = get_value_for_key(parse_data, &key)?;
+    Ok((key, value))
+}
+
+fn get_value_for_key(parse_data: &mut ParseData, key: &str) -> Result<WIPElement, Error> {
     parse_data.try_skip_comment();
     let value = match parse_data.next_non_whitespace_peek() {
-        Some('|'|'"') => {
-            parse_data.add_nested_name(key.clone());
+        Some('|' | '!' | '(') => {
+            parse_data.add_nested_name(key.to_string());
+            let res = get_value::expression_or_string(parse_data, TerminatorType::Struct)?;
+            parse_data.drop_last_nested_name();
+            res
+        }
+        Some(c) if (c.is_ascii_alphabetic() && get_value::is_function_call(parse_data)) || get_value::is_expression(parse_data) => {
+            parse_data.add_nested_name(key.to_string());
+            let res = get_value::expression_or_string(parse_data, TerminatorType::Struct)?;
+            parse_data.drop_last_nested_name();
+            res
+        }
+        Some(c) if get_value::is_string_start(parse_data, c) => {
+            parse_data.add_nested_name(key.to_string());
             let res = get_value::string(parse_data, TerminatorType::Struct)?.into();
             parse_data.drop_last_nested_name();
             res
         }
-        Some('t' | 'f') => get_value::bool(parse_data, TerminatorType::Struct)?.into(),
-        Some('<') => get_value::forced(parse_data, TerminatorType::Struct, &key)?,
+        Some('t' | 'f' | 'n' | 'i') => get_value::word(parse_data, TerminatorType::Struct)?.into(),
+        Some('<') => get_value::forced(parse_data, TerminatorType::Struct, key)?,
         Some('{') => {
-            parse_data.add_nested_name(key.clone());
+            parse_data.add_nested_name(key.to_string());
             let res = get_value::pml_struct(parse_data, TerminatorType::Struct)?.into();
             parse_data.drop_last_nested_name();
             res
         }
-        Some('.' | '-') => get_value::number(parse_data, TerminatorType::Struct)?.into(),
-        Some(c) if c.is_ascii_digit() => get_value::number(parse_data, TerminatorType::Struct)?.into(),
+        Some('[') => get_value::array(parse_data, key)?,
+        Some('.' | '-' | '+') => WIPElement::InferredNumber(get_value::number(parse_data, TerminatorType::Struct)?),
+        Some(c) if c.is_ascii_digit() => WIPElement::InferredNumber(get_value::number(parse_data, TerminatorType::Struct)?),
         Some(c) => Err(illegal_char_err(c, parse_data))?,
         None => Err(Error::UnexpectedEOF)?,
     };
-    Ok((key, value))
+    Ok(value)
 }
 
 fn get_quoted_key(parse_data: &mut ParseData) -> Result<String, Error> {
@@ -122,6 +198,78 @@ fn get_quoted_key(parse_data: &mut ParseData) -> Result<String, Error> {
     Err(Error::UnexpectedEOF)
 }
 
+/// Reads an escape sequence after a backslash, like `\n`, `\x41` or `\u{1F600}`. The line and
+/// column are the position of the backslash.
+fn escape_sequence(chars: &mut impl Iterator<Item = char>, line: u32, col: u32) -> Result<char, Error> {
+    let invalid = |value: String| Error::InvalidEscape{line, col, value: format!("\\{value}")};
+    match chars.next() {
+        Some('n') => Ok('\n'),
+        Some('t') => Ok('\t'),
+        Some('r') => Ok('\r'),
+        Some('0') => Ok('\0'),
+        Some(c @ ('\\' | '"')) => Ok(c),
+        Some('x') => {
+            let digits: String = chars.by_ref().take(2).collect();
+            match u8::from_str_radix(&digits, 16) {
+                Ok(b) if digits.len() == 2 && digits.chars().all(|c| c.is_ascii_hexdigit()) && b.is_ascii() => Ok(char::from(b)),
+                _ => Err(invalid(format!("x{digits}")))
+            }
+        }
+        Some('u') => {
+            if chars.next() != Some('{') {
+                return Err(invalid(String::from("u")));
+            }
+            let mut digits = String::new();
+            loop {
+                match chars.next() {
+                    Some('}') => break,
+                    Some(c) if c.is_ascii_hexdigit() && digits.len() < 6 => digits.push(c),
+                    Some(c) => return Err(invalid(format!("u{{{digits}{c}"))),
+                    None => return Err(Error::UnexpectedEOF)
+                }
+            }
+            u32::from_str_radix(&digits, 16).ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| invalid(format!("u{{{digits}}}")))
+        }
+        Some(c) => Err(invalid(c.to_string())),
+        None => Err(Error::UnexpectedEOF)
+    }
+}
+
+/// Reads the escape sequence after a backslash from the file.
+fn next_escape_sequence(parse_data: &mut ParseData) -> Result<char, Error> {
+    let (line, col) = (parse_data.line, parse_data.column);
+    escape_sequence(&mut iter::from_fn(|| parse_data.next_char()), line, col)
+}
+
+/// Reads a key of a map. Keys in quotes may contain any characters, quotes and backslashes have
+/// to be escaped.
+fn get_map_key(parse_data: &mut ParseData) -> Result<String, Error> {
+    match parse_data.next_non_whitespace_peek() {
+        Some('"') => parse_data.next_char(),
+        Some(c) if is_char_reserved(c) => return Err(illegal_char_err(c, parse_data)),
+        Some(_) => return get_unquoted_key(parse_data),
+        None => return Err(Error::UnexpectedEOF)
+    };
+    let mut key = String::new();
+    while let Some(c) = parse_data.next_char() {
+        match c {
+            '\\' => key.push(next_escape_sequence(parse_data)?),
+            '"' => {
+                parse_data.try_skip_comment();
+                return match parse_data.next_non_whitespace() {
+                    Some('=') => Ok(key),
+                    Some(c) => Err(illegal_char_err(c, parse_data)),
+                    None => Err(Error::UnexpectedEOF)
+                };
+            }
+            c => key.push(c)
+        }
+    }
+    Err(Error::UnexpectedEOF)
+}
+
 fn get_unquoted_key(parse_data: &mut ParseData) -> Result<String, Error> {
     let mut key = String::new();
     while let Some(c) = parse_data.next_char() {
@@ -187,10 +335,194 @@ fn get_meta_ident(parse_data: &mut ParseData) -> Result<String, Error> {
 
 fn handle_meta_info(parse_data: &mut ParseData, ident: &str, meta_info: &mut MetaInfo) -> Result<(), Error> {
     match ident {
-        "def" => meta_info.add_struct_template(parse_data),
+        "enum" => meta_info.add_enum(parse_data),
         _ => {
-            parse_data.skip_comment();
+            parse_data.rest_of_line();
             Ok(())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::string;
+    use crate::ParseError;
+
+    #[test]
+    fn parses_values_after_comments() {
+        let config = string("# first\n# second\na = <u8> 1; # trailing\nb = \"x\";\n").unwrap();
+        assert_eq!(config.get::<u8>("a").unwrap(), 1);
+        assert_eq!(config.get::<String>("b").unwrap(), "x");
+    }
+
+    #[test]
+    fn parses_null_values() {
+        let config = string("a = null; b = <u8?> null; c = <u8?> 3; d = <str?> null;").unwrap();
+        assert_eq!(config.get::<Option<u8>>("a").unwrap(), None);
+        assert_eq!(config.get::<Option<u8>>("b").unwrap(), None);
+        assert_eq!(config.get::<Option<u8>>("c").unwrap(), Some(3));
+        assert_eq!(config.get::<Option<String>>("d").unwrap(), None);
+        assert_eq!(config.get::<Option<u8>>("missing").unwrap(), None);
+        assert!(config.get::<Option<bool>>("c").is_err());
+    }
+
+    #[test]
+    fn rejects_nullable_arrays() {
+        let error = string("a = <u8?> [1, null];").unwrap_err();
+        assert!(matches!(error, ParseError::NullableArray{ref key, ref type_name} if key == ".a" && type_name == "u8"), "{error:?}");
+        assert!(string("a = <u8> [1, null];").is_err());
+    }
+
+    #[test]
+    fn range_checks_prefixed_literals() {
+        let config = string("a = <u8> 0xFF; b = <i8> 0x7F; c = <i8> -0x80; d = <i16> 0xFF;").unwrap();
+        assert_eq!(config.get::<u8>("a").unwrap(), 255);
+        assert_eq!(config.get::<i8>("b").unwrap(), 127);
+        assert_eq!(config.get::<i8>("c").unwrap(), -128);
+        assert_eq!(config.get::<i16>("d").unwrap(), 255);
+        assert!(matches!(string("a = <i8> 0xFF;"), Err(ParseError::ParseNumberError{..})));
+        assert!(matches!(string("a = <i8> [1, 0x80];"), Err(ParseError::ParseNumberError{..})));
+    }
+
+    #[test]
+    fn reports_literals_as_written() {
+        for input in ["a = <u8> 0x1FF;", "a = <u8> [0x1FF];", "a = <i8> 0x1FF;"] {
+            let error = string(input).unwrap_err();
+            assert!(matches!(error, ParseError::ParseNumberError{ref value, ..} if value == "0x1FF"), "{error:?}");
+        }
+        let error = string("a = <u8> 1_000;").unwrap_err();
+        assert!(matches!(error, ParseError::ParseNumberError{ref value, ..} if value == "1_000"), "{error:?}");
+    }
+
+    #[test]
+    fn strips_common_indentation_of_triple_quoted_strings() {
+        let config = string("a = \"\"\"\n    first\n      second\n\n    last\n    \"\"\";").unwrap();
+        assert_eq!(config.get::<String>("a").unwrap(), "first\n  second\n\nlast");
+        let config = string("a = \"\"\"one \"two\" three\"\"\";").unwrap();
+        assert_eq!(config.get::<String>("a").unwrap(), "one \"two\" three");
+    }
+
+    #[test]
+    fn compares_indentation_literally() {
+        let config = string("a = \"\"\"\n\tfirst\n    second\n\"\"\";").unwrap();
+        assert_eq!(config.get::<String>("a").unwrap(), "\tfirst\n    second");
+        let config = string("a = \"\"\"\n\t  first\n\t second\n\"\"\";").unwrap();
+        assert_eq!(config.get::<String>("a").unwrap(), " first\nsecond");
+    }
+
+    #[test]
+    fn parses_raw_strings_and_heredocs() {
+        let config = string("a = r\"C:\\dir\\n\"; b = r#\"say \"hi\"\"#; c = <<PEM\n    x\n      y\n    PEM;").unwrap();
+        assert_eq!(config.get::<String>("a").unwrap(), "C:\\dir\\n");
+        assert_eq!(config.get::<String>("b").unwrap(), "say \"hi\"");
+        assert_eq!(config.get::<String>("c").unwrap(), "x\n  y");
+    }
+
+    #[test]
+    fn spreads_into_typed_arrays() {
+        let config = string("a = <str> [\"x\"]; b = <str> [\"w\", ..|a|, \"y\"]; c = <u8> [1, ..|e|]; e = [];").unwrap();
+        assert_eq!(config.get::<Vec<String>>("b").unwrap(), ["w", "x", "y"]);
+        assert_eq!(config.get::<Vec<u8>>("c").unwrap(), [1]);
+        let error = string("a = [\"x\"]; b = <u8> [..|a|];").unwrap_err();
+        assert!(matches!(error, ParseError::SpreadTypeMismatch{ref expected, ref found, ..} if expected == "u8" && found == "str"), "{error:?}");
+    }
+
+    #[test]
+    fn spreads_into_nested_typed_arrays() {
+        let config = string("a = <u8> [[3]]; b = <u8> [[1, 2], ..|a|]; c = <u8> [..|a|, [4]];").unwrap();
+        assert_eq!(config.get::<Vec<Vec<u8>>>("b").unwrap(), [vec![1, 2], vec![3]]);
+        assert_eq!(config.get::<Vec<Vec<u8>>>("c").unwrap(), [vec![3], vec![4]]);
+        let error = string("a = <u8> [[3]]; b = <u8> [1, ..|a|];").unwrap_err();
+        assert!(matches!(error, ParseError::SpreadTypeMismatch{ref expected, ref found, ..} if expected == "[u8]" && found == "u8"), "{error:?}");
+    }
+
+    #[test]
+    fn resolves_references_in_typed_arrays() {
+        let config = string("n = 5; a = <u8> [|n|, 2]; b = <u16> [|n| * 100]; c = <b> [!|f|]; f = false;").unwrap();
+        assert_eq!(config.get::<Vec<u8>>("a").unwrap(), [5, 2]);
+        assert_eq!(config.get::<Vec<u16>>("b").unwrap(), [500]);
+        assert_eq!(config.get::<Vec<bool>>("c").unwrap(), [true]);
+        let error = string("n = 500; a = <u8> [|n|];").unwrap_err();
+        assert!(matches!(error, ParseError::SpreadTypeMismatch{ref found, ..} if found == "u16"), "{error:?}");
+    }
+
+    #[test]
+    fn widens_spread_numbers_like_concatenation() {
+        let config = string("a = <u8> [1, 2]; c = <u16> [300]; b = <u16> [..|a|, 300]; d = |a| + |c|;").unwrap();
+        assert_eq!(config.get::<Vec<u16>>("b").unwrap(), [1, 2, 300]);
+        assert_eq!(config.get_element("b").unwrap(), config.get_element("d").unwrap());
+        let config = string("a = <u8> [1]; b = <f64> [..|a|, 0.5];").unwrap();
+        assert_eq!(config.get::<Vec<f64>>("b").unwrap(), [1.0, 0.5]);
+        assert!(string("a = <i8> [-1]; b = <u8> [..|a|];").is_err());
+    }
+
+    #[test]
+    fn parses_special_floats() {
+        let config = string("a = inf; b = -inf; c = nan; d = <f64> -inf; e = <f32?> -nan; f = <f64> [inf, nan, 2]; g = +inf;").unwrap();
+        assert_eq!(config.get::<f32>("a").unwrap(), f32::INFINITY);
+        assert_eq!(config.get::<f32>("b").unwrap(), f32::NEG_INFINITY);
+        assert!(config.get::<f32>("c").unwrap().is_nan());
+        assert_eq!(config.get::<f64>("d").unwrap(), f64::NEG_INFINITY);
+        let e = config.get::<Option<f32>>("e").unwrap().unwrap();
+        assert!(e.is_nan() && e.is_sign_negative());
+        let f = config.get::<Vec<f64>>("f").unwrap();
+        assert_eq!(f[0], f64::INFINITY);
+        assert!(f[1].is_nan());
+        assert_eq!(config.get::<f32>("g").unwrap(), f32::INFINITY);
+        assert!(string("a = <u8> inf;").is_err());
+    }
+
+    #[test]
+    fn serializes_special_floats() {
+        let config = string("a = -nan; b = <f64> -inf; c = <f32> [nan, inf];").unwrap();
+        let serialized = string(&config.to_pml_string()).unwrap();
+        assert!(serialized.get::<f32>("a").unwrap().is_sign_negative());
+        assert_eq!(serialized.get::<f64>("b").unwrap(), f64::NEG_INFINITY);
+        assert!(serialized.get::<Vec<f32>>("c").unwrap()[0].is_nan());
+    }
+
+    #[test]
+    fn parses_escape_sequences() {
+        let config = string(r#"s = "a\r\nb\0c\\d\"e\u{1F600}f\x41g\t"; c = <char> "\u{e9}"; m = <map<str>> { "k\x42" = "v"; };"#).unwrap();
+        assert_eq!(config.get::<String>("s").unwrap(), "a\r\nb\0c\\d\"e\u{1F600}fAg\t");
+        assert_eq!(config.get::<char>("c").unwrap(), 'é');
+        assert_eq!(config.get::<String>("m[\"kB\"]").unwrap(), "v");
+    }
+
+    #[test]
+    fn rejects_invalid_escape_sequences() {
+        for (input, sequence) in [(r#"a = "x\q";"#, r"\q"), (r#"a = "\x80";"#, r"\x80"), (r#"a = "\u{110000}";"#, r"\u{110000}"), (r#"a = "\u41";"#, r"\u")] {
+            let error = string(input).unwrap_err();
+            assert!(matches!(error, ParseError::InvalidEscape{ref value, ..} if value == sequence), "{error:?}");
+        }
+        let error = string("a = 1;\nb = \"ok\\z\";").unwrap_err();
+        assert!(matches!(error, ParseError::InvalidEscape{line: 2, col: 8, ..}), "{error:?}");
+    }
+
+    #[test]
+    fn extends_structs() {
+        let config = string("a = { host = \"localhost\"; port = <u16> 80; limits = { cpu = 2; mem = 4; }; url = |.host| \":\" |.port|; };
+            b = |a| + { port = |a.port| + 1; limits = { cpu = 4; }; };
+            c = |b|
+            + { host = \"remote\"; };").unwrap();
+        assert_eq!(config.get::<u16>("b.port").unwrap(), 81);
+        assert_eq!(config.get::<String>("b.host").unwrap(), "localhost");
+        assert_eq!(config.get::<u8>("b.limits.cpu").unwrap(), 4);
+        assert_eq!(config.get::<u8>("b.limits.mem").unwrap(), 4);
+        assert_eq!(config.get::<String>("c.host").unwrap(), "remote");
+        assert_eq!(config.get::<String>("c.url").unwrap(), "localhost:80");
+        assert_eq!(config.get::<u8>("a.limits.cpu").unwrap(), 2);
+    }
+
+    #[test]
+    fn reports_invalid_extensions() {
+        let error = string("a = |b| + { x = 1; }; b = |a| + { y = 2; };").unwrap_err();
+        assert!(matches!(error, ParseError::CyclicExtension{ref keys} if keys.len() == 2), "{error:?}");
+        let error = string("s = { a = |.a| + { x = 1; }; };").unwrap_err();
+        assert!(matches!(error, ParseError::CyclicExtension{ref keys} if keys == &["s.a"]), "{error:?}");
+        let error = string("a = |missing| + { x = 1; };").unwrap_err();
+        assert!(matches!(error, ParseError::UnknownBase{ref key, ref base} if key == "a" && base == "missing"), "{error:?}");
+        let error = string("b = 1; a = |b| + { x = 1; };").unwrap_err();
+        assert!(matches!(error, ParseError::BaseNotAStruct{ref key, ref base} if key == "a" && base == "b"), "{error:?}");
+    }
+}
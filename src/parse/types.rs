@@ -1,16 +1,86 @@
-use std::{rc::Rc, cell::RefCell, collections::HashMap, iter::Peekable, str::Chars};
-use crate::{PmlStruct, Element, errors::ParseError as Error};
+use std::{rc::Rc, cell::RefCell, collections::{HashMap, HashSet}, iter::Peekable, str::Chars};
+use crate::{PmlStruct, Element, ArrayElement, errors::ParseError as Error, format_spec, functions::Functions, path::{self, Segment}};
+use super::{Expression, convert_number};
 
 type IncStringArray = Vec<(usize, Vec<ISElem>)>;
 type IncStructArray = Vec<(usize, Rc<RefCell<WIPStruct>>)>;
+type IncArray = Vec<WIPArrayItem>;
 
 #[derive(Debug)]
 pub(crate) enum WIPElement {
     Element(Element),
+    /// A number without forced type, whose type was inferred from its value.
+    InferredNumber(Element),
     IncompleteString(Vec<ISElem>),
     StringArray(IncStringArray),
     Struct(Rc<RefCell<WIPStruct>>),
     StructArray(IncStructArray),
+    Array(IncArray),
+    /// An array with a forced type that spreads other arrays into it, like
+    /// `<str> [..|hosts|, "extra"]`.
+    TypedArray(IncArray, ForcedArrayType),
+    Expression(Expression),
+}
+
+/// A value of an array without a forced type, whose type is inferred once all values are resolved.
+/// Nested arrays are inferred the same way, even if they have a forced type, so their values can
+/// be resolved together with the other arrays.
+#[derive(Debug)]
+pub(crate) enum WIPArrayItem {
+    Element(Element),
+    String(Vec<ISElem>),
+    Struct(Rc<RefCell<WIPStruct>>),
+    Array(Vec<WIPArrayItem>),
+    /// A nested array with a forced type that spreads other arrays into it. It keeps its type.
+    TypedArray(Vec<WIPArrayItem>, ForcedArrayType),
+    Expression(Expression),
+    /// An item like `..|hosts|`, which is replaced by the values of the array it evaluates to.
+    Spread(Expression),
+}
+
+/// The forced type of an array with values that are resolved later, like spread arrays. The
+/// values must have that type, or be numbers that can be converted to it.
+#[derive(Debug, Clone)]
+pub(crate) struct ForcedArrayType {
+    /// The full path of the array, for errors.
+    pub key: String,
+    /// The name of the type, like `str`.
+    pub name: String,
+    /// An empty array of the type, which the resolved values are inserted into.
+    pub empty: ArrayElement,
+}
+
+impl WIPArrayItem {
+    /// Checks if the item and all structs in it are resolved.
+    fn is_complete(&self) -> bool {
+        match self {
+            WIPArrayItem::Element(_) => true,
+            WIPArrayItem::Struct(s) => WIPStruct::is_complete_struct(s),
+            WIPArrayItem::Array(items) | WIPArrayItem::TypedArray(items, _) => items.iter().all(WIPArrayItem::is_complete),
+            WIPArrayItem::String(_) | WIPArrayItem::Expression(_) | WIPArrayItem::Spread(_) => false
+        }
+    }
+
+    /// Adds the structs of the items and their nested arrays.
+    fn collect_structs(items: &[WIPArrayItem], structs: &mut Vec<Rc<RefCell<WIPStruct>>>) {
+        for item in items {
+            match item {
+                WIPArrayItem::Struct(s) => structs.push(s.clone()),
+                WIPArrayItem::Array(inner) | WIPArrayItem::TypedArray(inner, _) => Self::collect_structs(inner, structs),
+                WIPArrayItem::Element(_) | WIPArrayItem::String(_) | WIPArrayItem::Expression(_) | WIPArrayItem::Spread(_) => ()
+            }
+        }
+    }
+
+    fn deep_copy(&self) -> WIPArrayItem {
+        match self {
+            WIPArrayItem::Element(elem) => WIPArrayItem::Element(elem.clone()),
+            WIPArrayItem::Struct(s) => WIPArrayItem::Struct(WIPStruct::copy_struct(s)),
+            WIPArrayItem::Array(items) => WIPArrayItem::Array(items.iter().map(WIPArrayItem::deep_copy).collect()),
+            WIPArrayItem::TypedArray(items, forced_type) => WIPArrayItem::TypedArray(items.iter().map(WIPArrayItem::deep_copy).collect(), forced_type.clone()),
+            WIPArrayItem::String(_) | WIPArrayItem::Expression(_) | WIPArrayItem::Spread(_) => unreachable!("Only complete arrays should be copied")
+        }
+    }
 }
 
 pub(crate) struct ParseData<'a> {
@@ -20,11 +90,15 @@ pub(crate) struct ParseData<'a> {
     nested_names: Vec<String>,
     pub nested_refs: Vec<Rc<RefCell<WIPStruct>>>,
     pub last_char: char,
+    pub meta_info: MetaInfo,
+    /// The functions that can be called in expressions.
+    pub functions: &'a Functions,
 }
 
 pub(crate) struct MetaInfo {
     version: Version,
-    struct_templates: HashMap<String, StructTemplate>,
+    /// The declared enums with their variants. Forced types refer to them by their index.
+    enums: Vec<(String, Vec<String>)>,
 }
 
 pub(crate) struct Version {
@@ -32,60 +106,61 @@ pub(crate) struct Version {
     pub minor: u8,
 }
 
-pub(crate) struct StructTemplate {
-}
-
-pub(crate) enum ArgumentTypes {
-    Bool,
-    PmlString,
-    Struct,
-    I8,
-    I16,
-    I32,
-    I64,
-    I128,
-    U8,
-    U16,
-    U32,
-    U64,
-    U128,
-    F32,
-    F64,
-    Array(ArrayTypes, usize),
-}
-
-pub(crate) enum ArrayTypes {
-    Bool,
-    PmlString,
-    Struct,
-    I8,
-    I16,
-    I32,
-    I64,
-    I128,
-    U8,
-    U16,
-    U32,
-    U64,
-    U128,
-    F32,
-    F64,
-}
-
 #[derive(Debug)]
 pub(crate) struct WIPStruct {
     pub(crate) finished_elements: HashMap<String, Element>,
+    /// The keys of numbers whose type was inferred, like `workers` in `workers = 4;`. In
+    /// expressions, they take the type of the other operand.
+    inferred_numbers: HashSet<String>,
     inc_strings: HashMap<String, Vec<ISElem>>,
     inc_string_arrays: HashMap<String, IncStringArray>,
     inc_structs: HashMap<String, Rc<RefCell<WIPStruct>>>,
     inc_struct_arrays: HashMap<String, IncStructArray>,
+    inc_arrays: HashMap<String, IncArray>,
+    /// The forced types of the arrays in `inc_arrays` that have one, because they spread other
+    /// arrays into them.
+    array_types: HashMap<String, ForcedArrayType>,
+    inc_expressions: HashMap<String, Expression>,
+    /// Whether the struct holds the values of a map, so it is resolved as [`Element::PmlMap`].
+    is_map: bool,
+    /// The struct this one extends, like `|worker_a|` in `worker_b = |worker_a| + { ... };`, until
+    /// its values are copied.
+    pub(crate) extends: Option<Extension>,
+}
+
+/// A reference to the struct that a struct extends.
+#[derive(Debug)]
+pub(crate) struct Extension {
+    /// The full path of the key of the extending struct, for errors.
+    pub key: String,
+    /// The struct the name of the base struct is relative to.
+    pub link: Rc<RefCell<WIPStruct>>,
+    /// The path of the base struct, like `worker_a`.
+    pub name: String,
 }
 
 
+/// The values of the struct that is currently being resolved, which can not be borrowed through
+/// its `Rc` while it is borrowed mutably.
+#[derive(Clone, Copy)]
+struct OwnValues<'a> {
+    finished_elements: &'a HashMap<String, Element>,
+    inferred_numbers: &'a HashSet<String>,
+    inc_structs: &'a HashMap<String, Rc<RefCell<WIPStruct>>>,
+}
+
 #[derive(Debug)]
 pub(crate) enum ISElem {
     Literal(String),
-    Variable(Rc<RefCell<WIPStruct>>, String),
+    Variable(Rc<RefCell<WIPStruct>>, String, Option<FormatSpec>),
+}
+
+/// The format spec of a variable in a string, like `05` in `|port:05|`.
+#[derive(Debug, Clone)]
+pub(crate) struct FormatSpec {
+    pub spec: String,
+    /// The line in which the spec appears, for errors when the variable is resolved.
+    pub line: u32,
 }
 
 #[derive(Clone, Copy)]
@@ -101,7 +176,7 @@ pub(super) enum TerminatorType {
 }
 
 impl<'a> ParseData<'a> {
-    pub fn init(input: &'a str) -> Self {
+    pub fn init(input: &'a str, functions: &'a Functions) -> Self {
         Self {
             line: 1,
             column: 0,
@@ -109,6 +184,8 @@ impl<'a> ParseData<'a> {
             nested_names: Vec::new(),
             nested_refs: Vec::new(),
             last_char: '\0',
+            meta_info: MetaInfo::init(),
+            functions,
         }
     }
 
@@ -139,6 +216,46 @@ impl<'a> ParseData<'a> {
         None
     }
 
+    pub fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    pub fn has_next(&mut self) -> bool {
+        self.chars.peek().is_some()
+    }
+
+    /// Checks if the next chars are the provided ones, without moving on.
+    pub fn upcoming(&self, s: &str) -> bool {
+        let mut chars = self.chars.clone();
+        s.chars().all(|c| chars.next() == Some(c))
+    }
+
+    /// Moves past the provided chars if they are next. Returns whether they were.
+    pub fn skip_upcoming(&mut self, s: &str) -> bool {
+        if !self.upcoming(s) {
+            return false;
+        }
+        self.skip_chars(s.chars().count());
+        true
+    }
+
+    pub fn skip_chars(&mut self, n: usize) {
+        for _ in 0..n {
+            self.next_char();
+        }
+    }
+
+    /// Returns the rest of the current line without moving on.
+    pub fn peek_line(&self) -> String {
+        self.chars.clone().take_while(|c| *c != '\n').collect()
+    }
+
+    /// Returns the first char that is not whitespace after skipping the provided number of chars,
+    /// without moving past it. It may be on one of the next lines.
+    pub fn peek_non_whitespace_after(&self, skip: usize) -> Option<char> {
+        self.chars.clone().skip(skip).find(|c| !c.is_whitespace())
+    }
+
     pub fn has_next_non_whitespace(&mut self) -> bool {
         self.next_non_whitespace_peek().is_some()
     }
@@ -153,10 +270,27 @@ impl<'a> ParseData<'a> {
         None
     }
 
+    /// Returns the rest of the current line and moves to the next one. Returns nothing if the
+    /// last char already ended the line.
+    pub fn rest_of_line(&mut self) -> String {
+        let mut line = String::new();
+        if self.last_char == '\n' {
+            return line;
+        }
+        while let Some(c) = self.next_char() {
+            if c == '\n' {
+                break;
+            }
+            line.push(c);
+        }
+        line
+    }
+
     pub fn skip_comment(&mut self) {
         while let Some(c) = self.next_char() {
             if c == '\n' {
                 self.try_skip_comment();
+                return;
             }
         }
     }
@@ -208,7 +342,7 @@ impl MetaInfo {
                 major: 1,
                 minor: 0,
             },
-            struct_templates: HashMap::new(),
+            enums: Vec::new(),
         }
     }
 
@@ -227,8 +361,41 @@ impl MetaInfo {
         Ok(())
     }
 
-    pub fn add_struct_template(&mut self, parse_data: &mut ParseData) -> Result<(), Error> {
-        Ok(())
+    /// Parses an enum declaration like `LogLevel: debug, info, warn`.
+    pub fn add_enum(&mut self, parse_data: &mut ParseData) -> Result<(), Error> {
+        let line = parse_data.line;
+        let text = parse_data.rest_of_line();
+        let is_ident = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        let declaration = text.split_once(':').and_then(|(name, variants)| {
+            let name = name.trim();
+            let variants: Vec<String> = variants.split(',').map(|v| v.trim().to_string()).collect();
+            let valid = is_ident(name)
+                && name.starts_with(|c: char| c.is_ascii_uppercase())
+                && self.enum_index(name).is_none()
+                && variants.iter().enumerate().all(|(i, v)| is_ident(v) && v != "null" && !variants[..i].contains(v));
+            valid.then(|| (name.to_string(), variants))
+        });
+        match declaration {
+            Some(declaration) => {
+                self.enums.push(declaration);
+                Ok(())
+            }
+            None => Err(Error::InvalidEnum {
+                line,
+                value: text.trim().to_string()
+            })
+        }
+    }
+
+    /// Returns the index of the enum with the provided name.
+    pub fn enum_index(&self, name: &str) -> Option<usize> {
+        self.enums.iter().position(|(n, _)| n == name)
+    }
+
+    /// Returns the name and the variants of the enum at the index.
+    pub fn enum_at(&self, index: usize) -> (&str, &[String]) {
+        let (name, variants) = &self.enums[index];
+        (name, variants)
     }
 }
 
@@ -236,10 +403,23 @@ impl WIPStruct {
     pub fn init() -> Self {
         Self {
             finished_elements: HashMap::new(),
+            inferred_numbers: HashSet::new(),
             inc_strings: HashMap::new(),
             inc_string_arrays: HashMap::new(),
             inc_structs: HashMap::new(),
             inc_struct_arrays: HashMap::new(),
+            inc_arrays: HashMap::new(),
+            array_types: HashMap::new(),
+            inc_expressions: HashMap::new(),
+            is_map: false,
+            extends: None,
+        }
+    }
+
+    pub fn init_map() -> Self {
+        Self {
+            is_map: true,
+            ..Self::init()
         }
     }
 
@@ -251,6 +431,14 @@ impl WIPStruct {
                     key,
                 })
             }
+            WIPElement::InferredNumber(elem) => match self.finished_elements.insert(key.clone(), elem) {
+                None => {
+                    self.inferred_numbers.insert(key);
+                }
+                Some(_) => return Err(Error::AlreadyExists {
+                    key,
+                })
+            }
             WIPElement::IncompleteString(inc_str) =>  match self.inc_strings.insert(key.clone(), inc_str) {
                 None => (),
                 Some(_) => return Err(Error::AlreadyExists {
@@ -275,118 +463,323 @@ impl WIPStruct {
                     key,
                 })
             },
+            WIPElement::Array(arr) => match self.inc_arrays.insert(key.clone(), arr) {
+                None => (),
+                Some(_) => return Err(Error::AlreadyExists {
+                    key,
+                })
+            },
+            WIPElement::TypedArray(arr, forced_type) => match self.inc_arrays.insert(key.clone(), arr) {
+                None => {
+                    self.array_types.insert(key, forced_type);
+                }
+                Some(_) => return Err(Error::AlreadyExists {
+                    key,
+                })
+            },
+            WIPElement::Expression(expr) => match self.inc_expressions.insert(key.clone(), expr) {
+                None => (),
+                Some(_) => return Err(Error::AlreadyExists {
+                    key,
+                })
+            },
         }
         Ok(())
     }
 
-    fn get_as_string_from_inc_struct(map: &HashMap<String, Rc<RefCell<WIPStruct>>>, key: &str) -> Option<String> {
-            let (first, rest) = key.split_once('.')?;
-            let wip_struct = map.get(first)?.borrow();
-            match rest.split_once('.') {
-                None => wip_struct.finished_elements.get(rest)?.try_into().ok(),
-                Some(_) => Self::get_as_string_from_inc_struct(&wip_struct.inc_structs, rest)
+    fn own_values(&self) -> OwnValues<'_> {
+        OwnValues {
+            finished_elements: &self.finished_elements,
+            inferred_numbers: &self.inferred_numbers,
+            inc_structs: &self.inc_structs
+        }
+    }
+
+    fn get_from_inc_struct(finished_elements: &HashMap<String, Element>, inferred_numbers: &HashSet<String>, inc_structs: &HashMap<String, Rc<RefCell<WIPStruct>>>, keys: &[&str]) -> Option<(Element, bool)> {
+        match keys {
+            [] => None,
+            [key] => finished_elements.get(*key).map(|elem| (elem.clone(), inferred_numbers.contains(*key))),
+            [first, rest @ ..] => {
+                let wip_struct = inc_structs.get(*first)?.borrow();
+                Self::get_from_inc_struct(&wip_struct.finished_elements, &wip_struct.inferred_numbers, &wip_struct.inc_structs, rest)
             }
+        }
     }
 
-    pub fn resolve_inc_strings(&mut self) -> (bool, bool) {
-        let mut no_change = true;
-        let mut incomplete_strings_temp: HashMap<String, Vec<ISElem>> = HashMap::new();
-        for (key, inc_str) in &self.inc_strings {
-            let mut accum_str = String::new();
-            let mut split: Vec<ISElem> = Vec::new();
-            for elem in inc_str {
-                match elem {
-                    ISElem::Literal(value) => accum_str.push_str(value),
-                    ISElem::Variable(map, name) => {
-                        if map.try_borrow().is_ok() {
-                            if let Some(val) = map.borrow().finished_elements.get::<String>(name) {
-                                accum_str.push_str(&val.to_string());
-                            }
-                            else if let Some(val) = Self::get_as_string_from_inc_struct(&map.borrow().inc_structs, name) {
-                                accum_str.push_str(&val.to_string());
-                            }
-                            else {
-                                split.push(ISElem::Literal(accum_str));
-                                accum_str = String::new();
-                                split.push(ISElem::Variable(map.clone(), name.clone()));
-                            }
-                        }
-                        else if let Some(val) = self.finished_elements.get::<String>(name) {
-                            accum_str.push_str(&val.to_string());
-                        }
-                        else if let Some(val) = Self::get_as_string_from_inc_struct(&self.inc_structs, name) {
-                            accum_str.push_str(&val.to_string());
+    /// Returns the value of a variable in the struct it references, if it is resolved already,
+    /// and whether it is a number whose type was inferred. The name is a path like `server.port`
+    /// or `hosts["example.com"]`. The struct that is currently being resolved is borrowed
+    /// mutably, so its elements are passed separately.
+    fn lookup(own: OwnValues<'_>, map: &Rc<RefCell<WIPStruct>>, name: &str) -> Option<(Element, bool)> {
+        let keys = path::parse(name)?.into_iter().map(|segment| match segment {
+            Segment::Key(key) => Some(key),
+            Segment::Index(_) => None
+        }).collect::<Option<Vec<_>>>()?;
+        match map.try_borrow() {
+            Ok(map) => Self::get_from_inc_struct(&map.finished_elements, &map.inferred_numbers, &map.inc_structs, &keys),
+            Err(_) => Self::get_from_inc_struct(own.finished_elements, own.inferred_numbers, own.inc_structs, &keys)
+        }
+    }
+
+    /// Tries to resolve all variables of an incomplete string. Returns the finished string, or the
+    /// partially resolved string if some variables could not be resolved yet. Fails if a format
+    /// spec does not fit the type of its variable.
+    fn resolve_inc_string(own: OwnValues<'_>, inc_str: &[ISElem]) -> Result<Result<String, Vec<ISElem>>, Error> {
+        let mut accum_str = String::new();
+        let mut split: Vec<ISElem> = Vec::new();
+        for elem in inc_str {
+            match elem {
+                ISElem::Literal(value) => accum_str.push_str(value),
+                ISElem::Variable(map, name, spec) => {
+                    match (Self::lookup(own, map, name).map(|(value, _)| value), spec) {
+                        (Some(val), None) => accum_str.push_str(&val.to_string()),
+                        (Some(val), Some(spec)) => match format_spec::format_element(&val, &spec.spec) {
+                            Some(formatted) => accum_str.push_str(&formatted),
+                            None => return Err(Error::InvalidFormatSpec{line: spec.line, spec: spec.spec.clone(), value_type: val.type_name()})
                         }
-                        else {
+                        (None, _) => {
                             split.push(ISElem::Literal(accum_str));
                             accum_str = String::new();
-                            split.push(ISElem::Variable(map.clone(), name.clone()));
+                            split.push(ISElem::Variable(map.clone(), name.clone(), spec.clone()));
                         }
                     }
                 }
             }
-            if split.is_empty() {
-                self.finished_elements.insert(key.clone(), accum_str.into());
-                no_change = false;
-            }
-            else {
-                split.push(ISElem::Literal(accum_str));
-                incomplete_strings_temp.insert(key.clone(), split);
+        }
+        if split.is_empty() {
+            Ok(Ok(accum_str))
+        }
+        else {
+            split.push(ISElem::Literal(accum_str));
+            Ok(Err(split))
+        }
+    }
+
+    pub fn resolve_inc_strings(&mut self) -> Result<(bool, bool), Error> {
+        let mut no_change = true;
+        let mut incomplete_strings_temp: HashMap<String, Vec<ISElem>> = HashMap::new();
+        for (key, inc_str) in &self.inc_strings {
+            match Self::resolve_inc_string(self.own_values(), inc_str)? {
+                Ok(string) => {
+                    self.finished_elements.insert(key.clone(), string.into());
+                    no_change = false;
+                }
+                Err(split) => {
+                    incomplete_strings_temp.insert(key.clone(), split);
+                }
             }
         }
         self.inc_strings = incomplete_strings_temp;
         let done = self.inc_strings.is_empty();
-        let (no_change2, done2) = self.resolve_inc_string_arrays();
-        (no_change && no_change2, done && done2)
+        let (no_change2, done2) = self.resolve_inc_string_arrays()?;
+        let (no_change3, done3) = self.resolve_inc_array_strings()?;
+        let (no_change4, done4) = self.resolve_inc_expressions()?;
+        let no_change5 = !self.resolve_extension();
+        let done5 = self.extends.is_none();
+        Ok((no_change && no_change2 && no_change3 && no_change4 && no_change5, done && done2 && done3 && done4 && done5))
+    }
+
+    /// Copies the values of the struct this one extends, once all of them are resolved. Returns
+    /// whether they were copied.
+    fn resolve_extension(&mut self) -> bool {
+        let Some(Extension { link, name, .. }) = &self.extends else {
+            return false;
+        };
+        let keys = path::parse(name).unwrap_or_default();
+        let base = match link.try_borrow() {
+            Ok(map) => Self::get_inc_struct(&map.inc_structs, &keys),
+            Err(_) => Self::get_inc_struct(&self.inc_structs, &keys)
+        };
+        let Some(base) = base.filter(Self::is_complete_struct) else {
+            return false;
+        };
+        let base = base.borrow().deep_copy();
+        self.extend_from(base);
+        self.extends = None;
+        true
+    }
+
+    fn get_inc_struct(map: &HashMap<String, Rc<RefCell<WIPStruct>>>, keys: &[Segment<'_>]) -> Option<Rc<RefCell<WIPStruct>>> {
+        match keys {
+            [Segment::Key(key)] => map.get(*key).cloned(),
+            [Segment::Key(first), rest @ ..] => {
+                let wip_struct = map.get(*first)?.try_borrow().ok()?;
+                Self::get_inc_struct(&wip_struct.inc_structs, rest)
+            }
+            _ => None
+        }
+    }
+
+    /// Checks if a value with the path exists in the struct or its nested structs, no matter if it
+    /// is resolved.
+    fn has_path(&self, keys: &[Segment<'_>]) -> bool {
+        match keys {
+            [Segment::Key(key)] => self.contains_key(key),
+            [Segment::Key(first), rest @ ..] => self.inc_structs.get(*first).is_some_and(|s| s.try_borrow().is_ok_and(|s| s.has_path(rest))),
+            _ => false
+        }
+    }
+
+    /// Explains why the extensions of the struct or its nested structs can not be resolved, once
+    /// nothing can be resolved anymore. Returns `None` if the extensions are not the reason.
+    pub(crate) fn extension_error(s: &Rc<RefCell<WIPStruct>>) -> Option<Error> {
+        if s.borrow().extends.is_some() {
+            if let Some(error) = Self::base_error(s) {
+                return Some(error);
+            }
+        }
+        s.borrow().inc_structs.values().find_map(Self::extension_error)
     }
 
-    fn resolve_inc_string_arrays(&mut self) -> (bool, bool) {
+    /// Follows the structs that the struct extends, until one of them does not exist, is not a
+    /// struct, or was already visited.
+    fn base_error(s: &Rc<RefCell<WIPStruct>>) -> Option<Error> {
+        let mut chain = vec![s.clone()];
+        let mut keys = Vec::new();
+        loop {
+            let current = chain.last().expect("The chain should never be empty").clone();
+            let current = current.borrow();
+            let Extension { key, link, name } = current.extends.as_ref()?;
+            keys.push(key.clone());
+            let path = path::parse(name).unwrap_or_default();
+            let base = link.try_borrow().ok().and_then(|link| Self::get_inc_struct(&link.inc_structs, &path));
+            let Some(base) = base else {
+                let exists = link.try_borrow().is_ok_and(|link| link.has_path(&path));
+                return Some(match exists {
+                    true => Error::BaseNotAStruct{key: key.clone(), base: name.clone()},
+                    false => Error::UnknownBase{key: key.clone(), base: name.clone()}
+                });
+            };
+            if let Some(start) = chain.iter().position(|s| Rc::ptr_eq(s, &base)) {
+                return Some(Error::CyclicExtension{keys: keys.split_off(start)});
+            }
+            drop(current);
+            chain.push(base);
+        }
+    }
+
+    fn is_complete_struct(s: &Rc<RefCell<WIPStruct>>) -> bool {
+        s.try_borrow().is_ok_and(|s| s.is_complete())
+    }
+
+    /// Checks if all values of the struct and its nested structs are resolved. A struct that is
+    /// being resolved right now is not complete, which prevents a struct from extending itself.
+    fn is_complete(&self) -> bool {
+        self.extends.is_none()
+            && self.inc_strings.is_empty()
+            && self.inc_string_arrays.is_empty()
+            && self.inc_expressions.is_empty()
+            && self.inc_arrays.values().all(|arr| arr.iter().all(WIPArrayItem::is_complete))
+            && self.inc_structs.values().all(Self::is_complete_struct)
+            && self.inc_struct_arrays.values().all(|arr| arr.iter().all(|(_, s)| Self::is_complete_struct(s)))
+    }
+
+    /// Copies the struct together with all nested structs. Only complete structs can be copied.
+    fn deep_copy(&self) -> WIPStruct {
+        WIPStruct {
+            finished_elements: self.finished_elements.clone(),
+            inferred_numbers: self.inferred_numbers.clone(),
+            inc_structs: self.inc_structs.iter().map(|(k, s)| (k.clone(), Self::copy_struct(s))).collect(),
+            inc_struct_arrays: self.inc_struct_arrays.iter()
+                .map(|(k, arr)| (k.clone(), arr.iter().map(|(i, s)| (*i, Self::copy_struct(s))).collect()))
+                .collect(),
+            inc_arrays: self.inc_arrays.iter().map(|(k, arr)| (k.clone(), arr.iter().map(WIPArrayItem::deep_copy).collect())).collect(),
+            array_types: self.array_types.clone(),
+            is_map: self.is_map,
+            ..WIPStruct::init()
+        }
+    }
+
+    fn copy_struct(s: &Rc<RefCell<WIPStruct>>) -> Rc<RefCell<WIPStruct>> {
+        Rc::new(RefCell::new(s.borrow().deep_copy()))
+    }
+
+    /// Adds the values of the base struct that this struct does not override. Nested structs that
+    /// both contain are extended recursively.
+    fn extend_from(&mut self, base: WIPStruct) {
+        for (key, s) in base.inc_structs {
+            match self.inc_structs.get(&key) {
+                Some(own) => own.borrow_mut().extend_from(s.replace(WIPStruct::init())),
+                None if !self.contains_key(&key) => {
+                    self.inc_structs.insert(key, s);
+                }
+                None => ()
+            }
+        }
+        for (key, elem) in base.finished_elements {
+            if !self.contains_key(&key) {
+                if base.inferred_numbers.contains(&key) {
+                    self.inferred_numbers.insert(key.clone());
+                }
+                self.finished_elements.insert(key, elem);
+            }
+        }
+        for (key, arr) in base.inc_struct_arrays {
+            if !self.contains_key(&key) {
+                self.inc_struct_arrays.insert(key, arr);
+            }
+        }
+        for (key, arr) in base.inc_arrays {
+            if !self.contains_key(&key) {
+                if let Some(forced_type) = base.array_types.get(&key) {
+                    self.array_types.insert(key.clone(), forced_type.clone());
+                }
+                self.inc_arrays.insert(key, arr);
+            }
+        }
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        self.finished_elements.contains_key(key)
+            || self.inc_strings.contains_key(key)
+            || self.inc_string_arrays.contains_key(key)
+            || self.inc_structs.contains_key(key)
+            || self.inc_struct_arrays.contains_key(key)
+            || self.inc_arrays.contains_key(key)
+            || self.inc_expressions.contains_key(key)
+    }
+
+    /// Evaluates all expressions whose referenced values are resolved.
+    fn resolve_inc_expressions(&mut self) -> Result<(bool, bool), Error> {
+        let mut no_change = true;
+        let mut inc_expressions_temp = HashMap::new();
+        for (key, expr) in self.inc_expressions.drain() {
+            let own = OwnValues {
+                finished_elements: &self.finished_elements,
+                inferred_numbers: &self.inferred_numbers,
+                inc_structs: &self.inc_structs
+            };
+            let lookup = |map: &Rc<RefCell<WIPStruct>>, name: &str| Self::lookup(own, map, name);
+            match expr.evaluate(&lookup)? {
+                Some(value) => {
+                    if expr.is_inferred(&lookup) {
+                        self.inferred_numbers.insert(key.clone());
+                    }
+                    self.finished_elements.insert(key, value);
+                    no_change = false;
+                }
+                None => {
+                    inc_expressions_temp.insert(key, expr);
+                }
+            }
+        }
+        self.inc_expressions = inc_expressions_temp;
+        Ok((no_change, self.inc_expressions.is_empty()))
+    }
+
+    fn resolve_inc_string_arrays(&mut self) -> Result<(bool, bool), Error> {
         let mut no_change = true;
         let mut incomplete_string_arrays_temp = HashMap::new();
         for (key, arr) in &self.inc_string_arrays {
             let mut array_temp_not_done = Vec::new();
             let mut array_temp_done = Vec::new();
             for (id, inc_str) in arr {
-                let mut accum_str = String::new();
-                let mut split: Vec<ISElem> = Vec::new();
-                for elem in inc_str {
-                    match elem {
-                        ISElem::Literal(value) => accum_str.push_str(value),
-                        ISElem::Variable(map, name) => {
-                            if map.try_borrow().is_ok() {
-                                if let Some(val) = map.borrow().finished_elements.get::<String>(name) {
-                                    accum_str.push_str(&val.to_string());
-                                }
-                                else if let Some(val) = Self::get_as_string_from_inc_struct(&map.borrow().inc_structs, name) {
-                                    accum_str.push_str(&val.to_string());
-                                }
-                                else {
-                                    split.push(ISElem::Literal(accum_str));
-                                    accum_str = String::new();
-                                    split.push(ISElem::Variable(map.clone(), name.clone()));
-                                }
-                            }
-                            else if let Some(val) = self.finished_elements.get::<String>(name) {
-                                accum_str.push_str(&val.to_string());
-                            }
-                            else if let Some(val) = Self::get_as_string_from_inc_struct(&self.inc_structs, name) {
-                                accum_str.push_str(&val.to_string());
-                            }
-                            else {
-                                split.push(ISElem::Literal(accum_str));
-                                accum_str = String::new();
-                                split.push(ISElem::Variable(map.clone(), name.clone()));
-                            }
-                        }
+                match Self::resolve_inc_string(self.own_values(), inc_str)? {
+                    Ok(string) => {
+                        array_temp_done.push((*id, string));
+                        no_change = false;
                     }
-                }
-                if split.is_empty() {
-                    array_temp_done.push((*id, accum_str));
-                    no_change = false;
-                }
-                else {
-                    split.push(ISElem::Literal(accum_str));
-                    array_temp_not_done.push((*id, split));
+                    Err(split) => array_temp_not_done.push((*id, split))
                 }
             }
             if array_temp_not_done.is_empty() {
@@ -399,21 +792,128 @@ impl WIPStruct {
         }
         self.inc_string_arrays = incomplete_string_arrays_temp;
         let done = self.inc_string_arrays.is_empty();
-        (no_change, done)
+        Ok((no_change, done))
     }
 
-    pub fn resolve_inc_strings_recursive(&self) -> (bool, bool) {
+    fn resolve_inc_array_strings(&mut self) -> Result<(bool, bool), Error> {
+        let mut no_change = true;
         let mut done = true;
+        let own = OwnValues {
+            finished_elements: &self.finished_elements,
+            inferred_numbers: &self.inferred_numbers,
+            inc_structs: &self.inc_structs
+        };
+        for arr in self.inc_arrays.values_mut() {
+            let (nc, d) = Self::resolve_array_item_strings(own, arr)?;
+            no_change &= nc;
+            done &= d;
+        }
+        // Arrays without unresolved items or structs are finished early, so they can be referenced.
+        let resolved: Vec<String> = self.inc_arrays.iter()
+            .filter(|(_, arr)| arr.iter().all(WIPArrayItem::is_complete))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in resolved {
+            let arr = self.inc_arrays.remove(&key).expect("The array should exist");
+            let arr = self.finish_array(&key, &arr)?;
+            self.finished_elements.insert(key, Element::PmlArray(arr));
+            no_change = false;
+        }
+        let resolved: Vec<String> = self.inc_struct_arrays.iter()
+            .filter(|(_, arr)| arr.iter().all(|(_, s)| Self::is_complete_struct(s)))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in resolved {
+            let arr = self.inc_struct_arrays.remove(&key).expect("The struct array should exist");
+            let mut temp_arr = Vec::new();
+            for (id, s) in arr {
+                temp_arr.push((id, Self::resolve_struct(&s)?));
+            }
+            self.finished_elements.insert(key, temp_arr.into());
+            no_change = false;
+        }
+        Ok((no_change, done))
+    }
+
+    fn resolve_array_item_strings(own: OwnValues<'_>, items: &mut Vec<WIPArrayItem>) -> Result<(bool, bool), Error> {
         let mut no_change = true;
-        for k in self.inc_structs.values() {
-            let (nc, d) = k.borrow_mut().resolve_inc_strings();
+        let mut done = true;
+        let mut i = 0;
+        while i < items.len() {
+            if let WIPArrayItem::Spread(expr) = &items[i] {
+                match expr.evaluate(&|map, name| Self::lookup(own, map, name))? {
+                    Some(Element::PmlArray(arr)) => {
+                        let values: Vec<WIPArrayItem> = (0..arr.len()).filter_map(|j| arr.get_element(j)).map(WIPArrayItem::Element).collect();
+                        let len = values.len();
+                        items.splice(i..=i, values);
+                        i += len;
+                        no_change = false;
+                    }
+                    Some(value) => return Err(Error::InvalidOperands {
+                        key: expr.key.clone(),
+                        operation: format!("..{}", value.type_name())
+                    }),
+                    None => {
+                        done = false;
+                        i += 1;
+                    }
+                }
+                continue;
+            }
+            let item = &mut items[i];
+            i += 1;
+            match item {
+                WIPArrayItem::String(inc_str) => match Self::resolve_inc_string(own, inc_str)? {
+                    Ok(string) => {
+                        *item = WIPArrayItem::Element(string.into());
+                        no_change = false;
+                    }
+                    Err(split) => {
+                        *inc_str = split;
+                        done = false;
+                    }
+                }
+                WIPArrayItem::Expression(expr) => match expr.evaluate(&|map, name| Self::lookup(own, map, name))? {
+                    Some(value) => {
+                        *item = WIPArrayItem::Element(value);
+                        no_change = false;
+                    }
+                    None => done = false
+                }
+                WIPArrayItem::Array(inner) | WIPArrayItem::TypedArray(inner, _) => {
+                    let (nc, d) = Self::resolve_array_item_strings(own, inner)?;
+                    no_change &= nc;
+                    done &= d;
+                }
+                WIPArrayItem::Element(_) | WIPArrayItem::Struct(_) | WIPArrayItem::Spread(_) => ()
+            }
+        }
+        Ok((no_change, done))
+    }
+
+    /// Returns the nested structs, including the structs in arrays, so they can be referenced
+    /// before the arrays are finished.
+    fn nested_structs(&self) -> Vec<Rc<RefCell<WIPStruct>>> {
+        let mut structs: Vec<_> = self.inc_structs.values().cloned().collect();
+        structs.extend(self.inc_struct_arrays.values().flatten().map(|(_, s)| s.clone()));
+        for arr in self.inc_arrays.values() {
+            WIPArrayItem::collect_structs(arr, &mut structs);
+        }
+        structs
+    }
+
+    pub fn resolve_inc_strings_recursive(&self) -> Result<(bool, bool), Error> {
+        let mut done = true;
+        let mut no_change = true;
+        for k in &self.nested_structs() {
+            let (nc, d) = k.borrow_mut().resolve_inc_strings()?;
             if !nc {
                 no_change = false;
             }
             if !d {
                 done = false;
             }
-            let (nc, d) = k.borrow().resolve_inc_strings_recursive();
+            let (nc, d) = k.borrow().resolve_inc_strings_recursive()?;
             if !nc {
                 no_change = false;
             }
@@ -421,7 +921,7 @@ impl WIPStruct {
                 done = false;
             }
         }
-        (no_change, done)
+        Ok((no_change, done))
     }
 
     pub fn resolve_inc_structs(&mut self) -> Result<PmlStruct, Error> {
@@ -430,7 +930,12 @@ impl WIPStruct {
             for (k, v) in  struct_arrays {
                 s.borrow_mut().finished_elements.insert(k, v);
             }
-            if self.finished_elements.insert(k.clone(), Element::PmlStruct(Box::new(s.borrow_mut().resolve_inc_structs()?))).is_some() {
+            let resolved = s.borrow_mut().resolve_inc_structs()?;
+            let elem = match s.borrow().is_map {
+                true => Element::PmlMap(resolved.elements.into_iter().collect()),
+                false => Element::PmlStruct(Box::new(resolved))
+            };
+            if self.finished_elements.insert(k.clone(), elem).is_some() {
                 return Err(Error::AlreadyExists{
                     key: k.to_string()
                 });
@@ -446,20 +951,99 @@ impl WIPStruct {
         for (key, arr) in &self.inc_struct_arrays {
             let mut temp_arr = Vec::new();
             for (id, s) in arr {
-                loop {
-                    let (no_change, done) = s.borrow_mut().resolve_inc_strings();
-                    let (no_change2, done2) = s.borrow().resolve_inc_strings_recursive();
-                    if done && done2 {
-                        break;
-                    }
-                    if no_change && no_change2{
-                        return Err(Error::IllegalDependency)
-                    }
-                }
-                temp_arr.push((*id, s.borrow_mut().resolve_inc_structs()?));
+                temp_arr.push((*id, Self::resolve_struct(s)?));
             }
             res.insert(key.clone(), temp_arr.into());
         }
+        for (key, arr) in &self.inc_arrays {
+            res.insert(key.clone(), Element::PmlArray(self.finish_array(key, arr)?));
+        }
         Ok(res)
     }
+
+    /// Creates the array with the key from its resolved values, either with its forced type or
+    /// with the type inferred from the values.
+    fn finish_array(&self, key: &str, items: &[WIPArrayItem]) -> Result<ArrayElement, Error> {
+        match self.array_types.get(key) {
+            Some(forced_type) => Self::resolve_typed_array(items, forced_type),
+            None => Self::resolve_array(items)
+        }
+    }
+
+    fn resolve_array(items: &[WIPArrayItem]) -> Result<ArrayElement, Error> {
+        Ok(ArrayElement::infer(Self::resolve_array_values(items)?))
+    }
+
+    /// Inserts the resolved values into an array with the forced type. Returns an error if one of
+    /// them has a different type, which can only be a value that was resolved later.
+    fn resolve_typed_array(items: &[WIPArrayItem], forced_type: &ForcedArrayType) -> Result<ArrayElement, Error> {
+        Self::typed_array(Self::resolve_array_values(items)?, forced_type)
+    }
+
+    /// Creates an array with the forced type from the values. Numbers are converted to that type
+    /// if they fit into it. If one of the values is an array, all of them have to be arrays of the
+    /// same type, whose values have the forced type.
+    fn typed_array(values: Vec<Element>, forced_type: &ForcedArrayType) -> Result<ArrayElement, Error> {
+        let mismatch = |expected: String, found: String| Error::SpreadTypeMismatch {
+            key: forced_type.key.clone(),
+            expected,
+            found
+        };
+        if values.iter().any(|value| matches!(value, Element::PmlArray(_))) {
+            let mut arrays: Vec<Element> = Vec::with_capacity(values.len());
+            for value in values {
+                let expected = arrays.first().map_or_else(|| format!("[{}]", forced_type.name), Element::type_name);
+                let Element::PmlArray(inner) = value else {
+                    return Err(mismatch(expected, value.type_name()));
+                };
+                let inner = (0..inner.len()).filter_map(|i| inner.get_element(i)).collect();
+                let array = Element::PmlArray(Self::typed_array(inner, forced_type)?);
+                if !arrays.is_empty() && array.type_name() != expected {
+                    return Err(mismatch(expected, array.type_name()));
+                }
+                arrays.push(array);
+            }
+            return Ok(ArrayElement::PmlArray(arrays));
+        }
+        let mut array = forced_type.empty.clone();
+        for value in values {
+            let value = convert_number(&value, &forced_type.name).unwrap_or(value);
+            let found = value.type_name();
+            if found != forced_type.name || array.insert_element(array.len(), value).is_err() {
+                return Err(mismatch(forced_type.name.clone(), found));
+            }
+        }
+        Ok(array)
+    }
+
+    fn resolve_array_values(items: &[WIPArrayItem]) -> Result<Vec<Element>, Error> {
+        let mut values = Vec::new();
+        for item in items {
+            values.push(match item {
+                WIPArrayItem::Element(elem) => elem.clone(),
+                WIPArrayItem::Struct(s) if s.borrow().is_map => Element::PmlMap(Self::resolve_struct(s)?.elements.into_iter().collect()),
+                WIPArrayItem::Struct(s) => Self::resolve_struct(s)?.into(),
+                WIPArrayItem::Array(inner) => Element::PmlArray(Self::resolve_array(inner)?),
+                WIPArrayItem::TypedArray(inner, forced_type) => Element::PmlArray(Self::resolve_typed_array(inner, forced_type)?),
+                WIPArrayItem::String(_) | WIPArrayItem::Expression(_) | WIPArrayItem::Spread(_) => unreachable!("All strings and expressions should have been resolved before")
+            });
+        }
+        Ok(values)
+    }
+
+    fn resolve_struct(s: &Rc<RefCell<WIPStruct>>) -> Result<PmlStruct, Error> {
+        loop {
+            let (no_change, done) = s.borrow_mut().resolve_inc_strings()?;
+            let (no_change2, done2) = s.borrow().resolve_inc_strings_recursive()?;
+            if done && done2 {
+                break;
+            }
+            if no_change && no_change2{
+                return Err(Self::extension_error(s).unwrap_or(Error::IllegalDependency))
+            }
+        }
+        let arrays = s.borrow().resolve_struct_arrays()?;
+        s.borrow_mut().finished_elements.extend(arrays);
+        s.borrow_mut().resolve_inc_structs()
+    }
 }
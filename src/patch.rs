@@ -0,0 +1,351 @@
+//! Types for applying changes to a [`PmlStruct`].
+//!
+//! See [`PmlStruct::apply`] for more information.
+
+use std::collections::BTreeMap;
+use crate::{Element, ArrayElement, PmlStruct, diff::{Diff, ChangeKind}, path::{self, Segment}, errors::{PatchError as Error, PatchFailure as Failure}};
+
+/// A list of operations that are [applied](PmlStruct::apply) in order.
+///
+/// Paths are dotted keys, where elements of arrays are addressed by their index, e.g.
+/// `servers[0].port` or `tags[2]`, and values of maps by their key in quotes, e.g.
+/// `hosts["example.com"]`.
+#[derive(Debug, Clone, Default)]
+pub struct Patch {
+    /// The operations of the patch.
+    pub operations: Vec<Operation>,
+}
+
+/// A single operation of a [`Patch`].
+#[derive(Debug, Clone)]
+pub enum Operation {
+    /// Sets the value at the path. Missing structs on the way are created, array elements must
+    /// exist and the value must have the type of the array.
+    Set {
+        /// The path of the value.
+        path: String,
+        /// The new value.
+        value: Element,
+        /// A condition the current value has to fulfill.
+        expected: Option<Precondition>,
+    },
+    /// Removes the value at the path, which has to exist.
+    Remove {
+        /// The path of the value.
+        path: String,
+        /// A condition the current value has to fulfill.
+        expected: Option<Precondition>,
+    },
+    /// Inserts a value into an array before the index the path ends with. The index may be equal to
+    /// the length of the array to append the value.
+    Insert {
+        /// The path of the value, ending with an index.
+        path: String,
+        /// The value to insert, which must have the type of the array.
+        value: Element,
+    },
+}
+
+/// A condition that has to hold for the current value before an [`Operation`] is applied. If a
+/// precondition is provided, the value has to exist.
+#[derive(Debug, Clone)]
+pub enum Precondition {
+    /// The current value has to be equal to the provided one.
+    Value(Element),
+    /// The current value has to have the provided [type](Element::type_name).
+    Type(String),
+}
+
+enum Container<'a> {
+    Struct(&'a mut PmlStruct),
+    Map(&'a mut BTreeMap<String, Element>),
+    Array(&'a mut ArrayElement),
+}
+
+impl PmlStruct {
+    /// Applies all operations of the patch in order.
+    ///
+    /// The patch is applied atomically: if one operation fails, none of the changes are kept.
+    ///
+    /// # Errors
+    /// This function returns an error containing the offending operation if a path is invalid or
+    /// does not exist, if a value does not have the type of its array, or if a precondition
+    /// does not hold.
+    pub fn apply(&mut self, patch: &Patch) -> Result<(), Error> {
+        let mut res = self.clone();
+        for (index, operation) in patch.operations.iter().enumerate() {
+            apply_operation(&mut res, operation).map_err(|reason| Error::Failed{index, operation: Box::new(operation.clone()), reason})?;
+        }
+        *self = res;
+        Ok(())
+    }
+}
+
+impl Operation {
+    /// Returns the path the operation applies to.
+    pub fn path(&self) -> &str {
+        match self {
+            Operation::Set{path, ..} |
+            Operation::Remove{path, ..} |
+            Operation::Insert{path, ..} => path
+        }
+    }
+}
+
+impl Patch {
+    /// Reads a patch from a struct with a struct array called `operations`.
+    ///
+    /// Every operation is a struct with an `op` that is one of `set`, `remove` or `insert`, the
+    /// `path` of the value, and the `value` for `set` and `insert`. Preconditions are provided as
+    /// either `expected` with the expected value or `expected_type` with the name of the
+    /// expected type.
+    ///
+    /// # Errors
+    /// This function returns an error if the struct does not describe a valid patch.
+    pub fn from_pml(pml_struct: &PmlStruct) -> Result<Self, Error> {
+        let Ok(operations) = pml_struct.get::<&Vec<PmlStruct>>("operations") else {
+            return Err(Error::InvalidOperation{index: 0});
+        };
+        let mut res = Patch::default();
+        for (index, s) in operations.iter().enumerate() {
+            let invalid = || Error::InvalidOperation{index};
+            let path = s.get::<String>("path").map_err(|_| invalid())?;
+            let value = s.elements.get("value").cloned();
+            let expected = match (s.elements.get("expected"), s.get::<String>("expected_type")) {
+                (Some(_), Ok(_)) => return Err(invalid()),
+                (Some(v), Err(_)) => Some(Precondition::Value(v.clone())),
+                (None, Ok(t)) => Some(Precondition::Type(t)),
+                (None, Err(_)) => None
+            };
+            res.operations.push(match (s.get::<&String>("op").map_err(|_| invalid())?.as_str(), value, expected) {
+                ("set", Some(value), expected) => Operation::Set{path, value, expected},
+                ("remove", None, expected) => Operation::Remove{path, expected},
+                ("insert", Some(value), None) => Operation::Insert{path, value},
+                _ => return Err(invalid())
+            });
+        }
+        Ok(res)
+    }
+
+    /// Serializes the patch to PML in the form that is read by [`from_pml`](Self::from_pml).
+    pub fn to_pml_string(&self) -> String {
+        let operations = self.operations.iter().map(|operation| {
+            let mut s = PmlStruct::new();
+            let (op, value, expected) = match operation {
+                Operation::Set{value, expected, ..} => ("set", Some(value), expected.as_ref()),
+                Operation::Remove{expected, ..} => ("remove", None, expected.as_ref()),
+                Operation::Insert{value, ..} => ("insert", Some(value), None)
+            };
+            s.elements.insert(String::from("op"), String::from(op).into());
+            s.elements.insert(String::from("path"), String::from(operation.path()).into());
+            if let Some(value) = value {
+                s.elements.insert(String::from("value"), value.clone());
+            }
+            match expected {
+                Some(Precondition::Value(v)) => {
+                    s.elements.insert(String::from("expected"), v.clone());
+                }
+                Some(Precondition::Type(t)) => {
+                    s.elements.insert(String::from("expected_type"), t.clone().into());
+                }
+                None => ()
+            }
+            s
+        }).collect();
+        let mut res = PmlStruct::new();
+        res.elements.insert(String::from("operations"), Element::PmlArray(ArrayElement::PmlStruct(operations)));
+        res.to_pml_string()
+    }
+}
+
+impl From<&Diff> for Patch {
+    /// Creates a patch that turns the old struct of the diff into the new one. Every operation
+    /// expects the old value.
+    fn from(diff: &Diff) -> Self {
+        let mut res = Patch::default();
+        let mut removed_array_elements = Vec::new();
+        for change in &diff.changes {
+            let path = change.path.clone();
+            match &change.kind {
+                ChangeKind::Added(value) if path::ends_with_index(&path) => res.operations.push(Operation::Insert{path, value: value.clone()}),
+                ChangeKind::Added(value) => res.operations.push(Operation::Set{path, value: value.clone(), expected: None}),
+                ChangeKind::Removed(old) => {
+                    let operation = Operation::Remove{path, expected: Some(Precondition::Value(old.clone()))};
+                    if path::ends_with_index(&change.path) {
+                        removed_array_elements.push(operation);
+                    }
+                    else {
+                        res.operations.push(operation);
+                    }
+                }
+                ChangeKind::Changed{old, new} |
+                ChangeKind::TypeChanged{old, new} => res.operations.push(Operation::Set{path, value: new.clone(), expected: Some(Precondition::Value(old.clone()))})
+            }
+        }
+        res.operations.extend(removed_array_elements.into_iter().rev());
+        res
+    }
+}
+
+fn check(expected: Option<&Precondition>, current: Option<Element>) -> Result<(), Failure> {
+    let holds = match (expected, &current) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(Precondition::Value(v)), Some(c)) => v == c,
+        (Some(Precondition::Type(t)), Some(c)) => *t == c.type_name()
+    };
+    if holds {
+        Ok(())
+    }
+    else {
+        Err(Failure::PreconditionFailed{actual: current})
+    }
+}
+
+fn apply_operation(root: &mut PmlStruct, operation: &Operation) -> Result<(), Failure> {
+    let segments = path::parse(operation.path()).ok_or(Failure::InvalidPath)?;
+    let (last, parents) = segments.split_last().ok_or(Failure::InvalidPath)?;
+    let container = walk(root, parents, matches!(operation, Operation::Set{..}))?;
+    match (operation, container, last) {
+        (Operation::Set{value, expected, ..}, Container::Struct(s), Segment::Key(k)) => {
+            check(expected.as_ref(), s.elements.get(*k).cloned())?;
+            s.elements.insert(String::from(*k), value.clone());
+        }
+        (Operation::Set{value, expected, ..}, Container::Map(m), Segment::Key(k)) => {
+            check(expected.as_ref(), m.get(*k).cloned())?;
+            m.insert(String::from(*k), value.clone());
+        }
+        (Operation::Set{value, expected, ..}, Container::Array(a), Segment::Index(i)) => {
+            if *i >= a.len() {
+                return Err(Failure::IndexOutOfBounds);
+            }
+            check(expected.as_ref(), a.get_element(*i))?;
+            a.set_element(*i, value.clone()).map_err(|_| Failure::TypeMismatch)?;
+        }
+        (Operation::Remove{expected, ..}, Container::Struct(s), Segment::Key(k)) => {
+            if !s.elements.contains_key(*k) {
+                return Err(Failure::NotFound);
+            }
+            check(expected.as_ref(), s.elements.get(*k).cloned())?;
+            s.elements.remove(*k);
+        }
+        (Operation::Remove{expected, ..}, Container::Map(m), Segment::Key(k)) => {
+            if !m.contains_key(*k) {
+                return Err(Failure::NotFound);
+            }
+            check(expected.as_ref(), m.get(*k).cloned())?;
+            m.remove(*k);
+        }
+        (Operation::Remove{expected, ..}, Container::Array(a), Segment::Index(i)) => {
+            if *i >= a.len() {
+                return Err(Failure::IndexOutOfBounds);
+            }
+            check(expected.as_ref(), a.get_element(*i))?;
+            a.remove_element(*i);
+        }
+        (Operation::Insert{value, ..}, Container::Array(a), Segment::Index(i)) => {
+            if *i > a.len() {
+                return Err(Failure::IndexOutOfBounds);
+            }
+            a.insert_element(*i, value.clone()).map_err(|_| Failure::TypeMismatch)?;
+        }
+        (Operation::Insert{..}, Container::Struct(_) | Container::Map(_), Segment::Key(_)) => return Err(Failure::NotAnArray),
+        (_, Container::Struct(_) | Container::Map(_), Segment::Index(_)) => return Err(Failure::NotAnArray),
+        (_, Container::Array(_), Segment::Key(_)) => return Err(Failure::NotAStruct)
+    }
+    Ok(())
+}
+
+fn walk<'a>(root: &'a mut PmlStruct, segments: &[Segment], create: bool) -> Result<Container<'a>, Failure> {
+    let mut container = Container::Struct(root);
+    for segment in segments {
+        container = match (container, segment) {
+            (Container::Struct(s), Segment::Key(k)) => {
+                if create && !s.elements.contains_key(*k) {
+                    s.elements.insert(String::from(*k), PmlStruct::new().into());
+                }
+                child(s.elements.get_mut(*k), Failure::NotFound)?
+            }
+            (Container::Map(m), Segment::Key(k)) => {
+                if create && !m.contains_key(*k) {
+                    m.insert(String::from(*k), PmlStruct::new().into());
+                }
+                child(m.get_mut(*k), Failure::NotFound)?
+            }
+            (Container::Array(ArrayElement::PmlStruct(a)), Segment::Index(i)) => Container::Struct(a.get_mut(*i).ok_or(Failure::IndexOutOfBounds)?),
+            (Container::Array(ArrayElement::PmlArray(a) | ArrayElement::PmlMixed(a)), Segment::Index(i)) => child(a.get_mut(*i), Failure::IndexOutOfBounds)?,
+            (Container::Array(_), Segment::Index(_)) => return Err(Failure::NotAStruct),
+            (Container::Struct(_) | Container::Map(_), Segment::Index(_)) => return Err(Failure::NotAnArray),
+            (Container::Array(_), Segment::Key(_)) => return Err(Failure::NotAStruct)
+        }
+    }
+    Ok(container)
+}
+
+/// Returns the value as container, or the provided failure if it does not exist.
+fn child(value: Option<&mut Element>, missing: Failure) -> Result<Container<'_>, Failure> {
+    match value {
+        Some(Element::PmlStruct(s)) => Ok(Container::Struct(s)),
+        Some(Element::PmlMap(m)) => Ok(Container::Map(m)),
+        Some(Element::PmlArray(a)) => Ok(Container::Array(a)),
+        Some(_) => Err(Failure::NotAStruct),
+        None => Err(missing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, diff::diff, PmlStruct, errors::{PatchError, PatchFailure}};
+    use super::{Patch, Operation, Precondition};
+
+    fn pml(input: &str) -> PmlStruct {
+        parse::string(input).expect("The input should be valid")
+    }
+
+    #[test]
+    fn applies_operations_in_order() {
+        let mut config = pml("tags = <str> [\"a\"]; servers = [{port = <u16> 80;}];");
+        let patch = Patch{operations: vec![
+            Operation::Insert{path: String::from("tags[1]"), value: String::from("b").into()},
+            Operation::Set{path: String::from("servers[0].port"), value: 8080u16.into(), expected: Some(Precondition::Value(80u16.into()))},
+            Operation::Set{path: String::from("db.host"), value: String::from("x").into(), expected: None},
+        ]};
+        config.apply(&patch).unwrap();
+        assert_eq!(config, pml("tags = <str> [\"a\", \"b\"]; servers = [{port = <u16> 8080;}]; db = { host = \"x\"; };"));
+    }
+
+    #[test]
+    fn is_atomic() {
+        let mut config = pml("a = <u8> 1; tags = <str> [\"a\"];");
+        let patch = Patch{operations: vec![
+            Operation::Remove{path: String::from("a"), expected: None},
+            Operation::Insert{path: String::from("tags[0]"), value: 1u8.into()},
+        ]};
+        let res = config.apply(&patch);
+        assert!(matches!(res, Err(PatchError::Failed{index: 1, reason: PatchFailure::TypeMismatch, ..})));
+        assert_eq!(config, pml("a = <u8> 1; tags = <str> [\"a\"];"));
+    }
+
+    #[test]
+    fn changes_map_entries() {
+        let mut config = pml("ports = <map<u16>> { \"a.com\" = 80; \"b.com\" = 81; };");
+        let patch = Patch{operations: vec![
+            Operation::Set{path: String::from("ports[\"a.com\"]"), value: 8080u16.into(), expected: Some(Precondition::Type(String::from("u16")))},
+            Operation::Remove{path: String::from("ports[\"b.com\"]"), expected: None},
+        ]};
+        config.apply(&patch).unwrap();
+        assert_eq!(config, pml("ports = <map<u16>> { \"a.com\" = 8080; };"));
+    }
+
+    #[test]
+    fn turns_diffs_into_patches() {
+        let old = pml("a = <u8> 1; tags = <str> [\"a\", \"b\"]; servers = [{id = <u8> 1;}, {id = <u8> 2;}]; m = <map<str>> { \"x\" = \"1\"; };");
+        let new = pml("a = <u8> 2; tags = <str> [\"a\"]; servers = [{id = <u8> 1;}]; m = <map<str>> { \"y\" = \"2\"; };");
+        let patch = Patch::from(&diff(&old, &new));
+        let mut patched = old.clone();
+        patched.apply(&patch).unwrap();
+        assert_eq!(patched, new);
+        let round_trip = Patch::from_pml(&pml(&patch.to_pml_string())).unwrap();
+        assert_eq!(round_trip.operations.len(), patch.operations.len());
+    }
+}
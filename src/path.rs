@@ -0,0 +1,60 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// Appends the key of a struct value to the path, like `db` and `host` to `db.host`.
+pub(crate) fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        String::from(key)
+    }
+    else {
+        format!("{path}.{key}")
+    }
+}
+
+/// Appends the key of a map value to the path, like `hosts` and `example.com` to
+/// `hosts["example.com"]`.
+pub(crate) fn join_map_key(path: &str, key: &str) -> String {
+    format!("{path}[\"{key}\"]")
+}
+
+/// Checks if the last segment of the path is an index, like in `servers[0]`.
+pub(crate) fn ends_with_index(path: &str) -> bool {
+    matches!(parse(path).as_deref().and_then(<[Segment]>::last), Some(Segment::Index(_)))
+}
+
+/// Splits a path like `servers[0].ports[1]` or `hosts["example.com"]` into its segments. Keys in
+/// quotes may contain any characters except for `"]`. Returns `None` if the path is invalid.
+pub(crate) fn parse(path: &str) -> Option<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    let mut rest = path;
+    loop {
+        let end = rest.find(['.', '[']).unwrap_or(rest.len());
+        if end == 0 {
+            return None;
+        }
+        segments.push(Segment::Key(&rest[..end]));
+        rest = &rest[end..];
+        while let Some(brackets) = rest.strip_prefix('[') {
+            if let Some(quoted) = brackets.strip_prefix('"') {
+                let (key, after) = quoted.split_once("\"]")?;
+                segments.push(Segment::Key(key));
+                rest = after;
+                continue;
+            }
+            let (index, after) = brackets.split_once(']')?;
+            if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            segments.push(Segment::Index(index.parse().ok()?));
+            rest = after;
+        }
+        match rest.strip_prefix('.') {
+            Some(after) => rest = after,
+            None if rest.is_empty() => return Some(segments),
+            None => return None
+        }
+    }
+}
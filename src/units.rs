@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+const DURATION_UNITS: [(&str, u128); 7] = [
+    ("d", 86_400_000_000_000),
+    ("h", 3_600_000_000_000),
+    ("m", 60_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+const BYTE_UNITS: [(&str, u128); 11] = [
+    ("PiB", 1 << 50),
+    ("TiB", 1 << 40),
+    ("GiB", 1 << 30),
+    ("MiB", 1 << 20),
+    ("KiB", 1 << 10),
+    ("PB", 1_000_000_000_000_000),
+    ("TB", 1_000_000_000_000),
+    ("GB", 1_000_000_000),
+    ("MB", 1_000_000),
+    ("KB", 1_000),
+    ("B", 1),
+];
+
+/// Parses a duration like `250ms` or `1h30m`. Units have to be in descending order.
+pub(crate) fn parse_duration(value: &str) -> Option<Duration> {
+    let mut nanos = 0u128;
+    let mut smallest_unit = None;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let (amount, unit, remaining) = split_quantity(rest)?;
+        let position = DURATION_UNITS.iter().position(|(u, _)| *u == unit)?;
+        if smallest_unit.is_some_and(|p| p >= position) {
+            return None;
+        }
+        smallest_unit = Some(position);
+        nanos = nanos.checked_add(apply_unit(amount, DURATION_UNITS[position].1)?)?;
+        rest = remaining;
+    }
+    smallest_unit?;
+    let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+    Some(Duration::new(secs, u32::try_from(nanos % 1_000_000_000).ok()?))
+}
+
+/// Parses a byte size like `64KiB` or `2GB` and returns the number of bytes.
+pub(crate) fn parse_byte_size(value: &str) -> Option<u64> {
+    let (amount, unit, rest) = split_quantity(value)?;
+    if !rest.is_empty() {
+        return None;
+    }
+    let (_, factor) = BYTE_UNITS.iter().find(|(u, _)| *u == unit)?;
+    u64::try_from(apply_unit(amount, *factor)?).ok()
+}
+
+/// Writes the duration with the biggest units possible, like `1h30m` or `1s500ms`.
+pub(crate) fn format_duration(duration: &Duration) -> String {
+    let mut nanos = duration.as_nanos();
+    if nanos == 0 {
+        return String::from("0s");
+    }
+    let mut res = String::new();
+    for (unit, factor) in DURATION_UNITS {
+        if nanos >= factor {
+            res.push_str(&(nanos / factor).to_string());
+            res.push_str(unit);
+            nanos %= factor;
+        }
+    }
+    res
+}
+
+/// Writes the number of bytes with the unit that results in the smallest whole amount, like
+/// `64KiB` or `2GB`.
+pub(crate) fn format_byte_size(bytes: u64) -> String {
+    let bytes = u128::from(bytes);
+    let (unit, factor) = BYTE_UNITS.iter()
+        .filter(|(_, factor)| bytes != 0 && bytes % factor == 0)
+        .min_by_key(|(_, factor)| bytes / factor)
+        .unwrap_or(&("B", 1));
+    format!("{}{unit}", bytes / factor)
+}
+
+/// Splits a value like `1.5h30m` into the amount, the unit and the rest.
+fn split_quantity(value: &str) -> Option<(&str, &str, &str)> {
+    let unit_start = value.find(|c: char| c.is_ascii_alphabetic())?;
+    let unit_end = value[unit_start..].find(|c: char| !c.is_ascii_alphabetic()).map_or(value.len(), |i| unit_start + i);
+    Some((&value[..unit_start], &value[unit_start..unit_end], &value[unit_end..]))
+}
+
+/// Multiplies an amount like `1.5` with the factor of its unit. Returns `None` if the amount is
+/// invalid or the result is not a whole number.
+fn apply_unit(amount: &str, factor: u128) -> Option<u128> {
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    if whole.is_empty() || !whole.bytes().all(|b| b.is_ascii_digit()) || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let whole = whole.parse::<u128>().ok()?.checked_mul(factor)?;
+    if fraction.is_empty() {
+        return Some(whole);
+    }
+    let divisor = 10u128.checked_pow(u32::try_from(fraction.len()).ok()?)?;
+    let fraction = fraction.parse::<u128>().ok()?.checked_mul(factor)?;
+    if fraction % divisor != 0 {
+        return None;
+    }
+    whole.checked_add(fraction / divisor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_byte_size, format_duration, parse_byte_size, parse_duration};
+    use crate::{parse, ParseError};
+    use std::time::Duration;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("30m1h"), None);
+        assert_eq!(parse_duration("1s1s"), None);
+        assert_eq!(parse_duration("5x"), None);
+    }
+
+    #[test]
+    fn parses_byte_sizes() {
+        assert_eq!(parse_byte_size("64KiB"), Some(65_536));
+        assert_eq!(parse_byte_size("2GB"), Some(2_000_000_000));
+        assert_eq!(parse_byte_size("1.5GiB"), Some(1_610_612_736));
+        assert_eq!(parse_byte_size("1KB1B"), None);
+        assert_eq!(parse_byte_size("100000PiB"), None);
+    }
+
+    #[test]
+    fn formats_with_the_biggest_units() {
+        assert_eq!(format_duration(&Duration::from_secs(5400)), "1h30m");
+        assert_eq!(format_duration(&Duration::from_millis(1500)), "1s500ms");
+        assert_eq!(format_duration(&Duration::ZERO), "0s");
+        assert_eq!(format_byte_size(65_536), "64KiB");
+        assert_eq!(format_byte_size(2_000_000_000), "2GB");
+        assert_eq!(format_byte_size(1000), "1KB");
+    }
+
+    #[test]
+    fn reads_literals_with_units() {
+        let config = parse::string("timeout = 30s; buf = 64KiB;").unwrap();
+        assert_eq!(config.get::<Duration>("timeout").unwrap(), Duration::from_secs(30));
+        assert_eq!(config.get::<u64>("buf").unwrap(), 65_536);
+        assert!(matches!(parse::string("a = 5parsecs;"), Err(ParseError::InvalidUnit{..})));
+    }
+}